@@ -0,0 +1,85 @@
+//! Broadphase benchmark
+//!
+//! Scatters thousands of threats and projectiles over the playfield and
+//! finds every overlapping pair twice: through `SpatialGrid` (rebuild plus
+//! one query per projectile, as the hits system does each tick) and by
+//! testing every pair. Both must find the same pairs; the timings show how
+//! far the grid keeps bullet-hell waves inside a frame.
+//!
+//! `cargo bench --bench collisions`
+
+use std::hint::black_box;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use renegade::constants::*;
+use renegade::game::broadphase::SpatialGrid;
+use renegade::game::physics::check_collision;
+use renegade::rng::Rng;
+
+type Circle = (f64, f64, f64);
+
+const SIZES: [usize; 4] = [100, 500, 2_000, 5_000];
+const ROUNDS: u32 = 20;
+
+fn scatter(rng: &mut Rng, count: usize, radius: (f64, f64)) -> Vec<Circle> {
+    (0..count)
+        .map(|_| {
+            let x = rng.range(-100.0, WORLD_WIDTH + 100.0);
+            let y = rng.range(-100.0, WORLD_HEIGHT + 100.0);
+            (x, y, rng.range(radius.0, radius.1))
+        })
+        .collect()
+}
+
+fn brute_force(threats: &[Circle], projectiles: &[Circle]) -> usize {
+    projectiles
+        .iter()
+        .map(|&(px, py, pr)| threats.iter().filter(|&&(tx, ty, tr)| check_collision(px, py, pr, tx, ty, tr)).count())
+        .sum()
+}
+
+fn with_grid(grid: &mut SpatialGrid, threats: &[Circle], projectiles: &[Circle]) -> usize {
+    grid.rebuild(threats.iter().enumerate().map(|(i, &(x, y, r))| (i as u32, x, y, r)));
+    projectiles
+        .iter()
+        .map(|&(px, py, pr)| {
+            grid.query(px, py, pr)
+                .filter(|&i| {
+                    let (tx, ty, tr) = threats[i as usize];
+                    check_collision(px, py, pr, tx, ty, tr)
+                })
+                .count()
+        })
+        .sum()
+}
+
+/// Mean time per round and the pair count from the last one
+fn time(mut run: impl FnMut() -> usize) -> (Duration, usize) {
+    let mut pairs = 0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        pairs = black_box(run());
+    }
+    (start.elapsed() / ROUNDS, pairs)
+}
+
+fn main() -> ExitCode {
+    let mut rng = Rng::new(0xC011);
+    let mut ok = true;
+    println!("{:>6} entities each | {:>12} | {:>12} | pairs", "", "brute force", "grid");
+    for count in SIZES {
+        let threats = scatter(&mut rng, count, (12.0, 40.0));
+        let projectiles = scatter(&mut rng, count, (5.0, 5.0));
+        let mut grid = SpatialGrid::with_capacity(count);
+
+        let (brute_time, brute_pairs) = time(|| brute_force(&threats, &projectiles));
+        let (grid_time, grid_pairs) = time(|| with_grid(&mut grid, &threats, &projectiles));
+        println!("{count:>6} entities each | {brute_time:>12.2?} | {grid_time:>12.2?} | {grid_pairs}");
+
+        if grid_pairs != brute_pairs {
+            eprintln!("collisions: grid found {grid_pairs} pairs, brute force {brute_pairs}");
+            ok = false;
+        }
+    }
+    if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
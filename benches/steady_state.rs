@@ -0,0 +1,88 @@
+//! Steady-state simulation benchmark
+//!
+//! Plays a busy game headless (constant fire, waves of threats, extra
+//! explosions to keep the particle pool saturated) until every pool has
+//! warmed up, then times a run of ticks while counting heap allocations.
+//! Entity storage is preallocated, so that count should be zero; the
+//! benchmark exits with an error if it isn't.
+//!
+//! `cargo bench --bench steady_state`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use renegade::game::ecs::Group;
+use renegade::game::particles::Effect;
+use renegade::game::{Capacities, GameState};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const TICK: f64 = 1.0 / 60.0;
+const WARMUP_TICKS: u32 = 3_000;
+const MEASURED_TICKS: u32 = 20_000;
+
+/// One frame of a hectic game; the planet is kept alive so it never ends
+fn tick(state: &mut GameState, n: u32) {
+    if n.is_multiple_of(4) {
+        state.shoot();
+    }
+    let (x, y) = state.player.get_position();
+    state.emit(Effect::Explosion, x, y, 0.0);
+    state.emit(Effect::Smoke, x, y, 0.0);
+    state.emit(Effect::Debris, x, y, 0.0);
+    state.planet_health = 100;
+    state.update(TICK);
+}
+
+fn main() -> ExitCode {
+    // A small particle cap, so the pool spends the run recycling
+    let capacities = Capacities { particles: 512, ..Capacities::default() };
+    let mut state = GameState::with_capacities(0xBE7C, capacities);
+    for n in 0..WARMUP_TICKS {
+        tick(&mut state, n);
+    }
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut peak_particles = 0;
+    for n in WARMUP_TICKS..WARMUP_TICKS + MEASURED_TICKS {
+        tick(&mut state, n);
+        peak_particles = peak_particles.max(state.world.len(Group::Particles));
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    println!(
+        "steady_state: {MEASURED_TICKS} ticks, {:.2} us/tick, peak {peak_particles}/{} particles, {allocations} allocations",
+        elapsed.as_secs_f64() * 1e6 / MEASURED_TICKS as f64,
+        state.world.capacity(Group::Particles),
+    );
+
+    if allocations > 0 {
+        eprintln!("steady_state: expected no allocations once the pools are warm");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
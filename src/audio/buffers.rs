@@ -0,0 +1,194 @@
+//! Decoded sample cache
+//!
+//! Every asset listed in the manifest is fetched and decoded once at
+//! startup into an `AudioBuffer`;
+//! each play is then just a cheap `AudioBufferSourceNode` on the mixer.
+//! Voices are capped per sound so rapid fire can't pile up hundreds of
+//! overlapping sources: the oldest voice is cut when the cap is reached.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    AbortController, AudioBuffer, AudioBufferSourceNode, AudioNode, AudioScheduledSourceNode, HtmlMediaElement, RequestInit,
+    Response,
+};
+
+use crate::audio::manifest;
+use crate::audio::mixer::{with_mixer, Mixer};
+use crate::audio::spatial::Placement;
+use crate::audio::Sound;
+
+/// A download still going after this long is abandoned as failed, so a hung
+/// request can't hold the loading screen forever
+const FETCH_TIMEOUT_MS: i32 = 10_000;
+
+thread_local! {
+    static BUFFERS: RefCell<HashMap<Sound, AudioBuffer>> = RefCell::new(HashMap::new());
+    static VOICES: RefCell<HashMap<Sound, VecDeque<Voice>>> = RefCell::new(HashMap::new());
+    static LOADED: Cell<usize> = const { Cell::new(0) };
+    static TOTAL: Cell<usize> = const { Cell::new(0) };
+}
+
+struct Voice {
+    source: AudioBufferSourceNode,
+    ends_at: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PreloadProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl PreloadProgress {
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 { 1.0 } else { self.loaded as f64 / self.total as f64 }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.loaded >= self.total
+    }
+}
+
+pub fn progress() -> PreloadProgress {
+    PreloadProgress {
+        loaded: LOADED.with(Cell::get),
+        total: TOTAL.with(Cell::get),
+    }
+}
+
+/// Kick off fetching and decoding every sound that has asset files.
+/// Failures still count towards progress so the loading screen never hangs.
+pub fn preload(sounds: &[Sound]) {
+    for &sound in sounds {
+        let files = &manifest::entry(sound).files;
+        if files.is_empty() {
+            continue;
+        }
+        TOTAL.with(|t| t.set(t.get() + 1));
+        wasm_bindgen_futures::spawn_local(async move {
+            match load_first_playable(files).await {
+                Ok(buffer) => {
+                    BUFFERS.with(|b| b.borrow_mut().insert(sound, buffer));
+                }
+                Err(failures) => {
+                    // One warning per missing asset, at load time, rather than on every play
+                    log::warn!("missing asset sound={:?} fallback=synth tried=[{}]", sound, failures.join(", "));
+                }
+            }
+            LOADED.with(|l| l.set(l.get() + 1));
+        });
+    }
+}
+
+/// Try each candidate in order, skipping formats the browser can't decode.
+/// On failure, returns a `file: reason` line per candidate.
+async fn load_first_playable(files: &[String]) -> Result<AudioBuffer, Vec<String>> {
+    let mut failures: Vec<String> = files
+        .iter()
+        .filter(|file| !manifest::mime_type(file).is_some_and(can_play_type))
+        .map(|file| format!("{file}: unsupported format"))
+        .collect();
+    for file in manifest::playable(files, can_play_type) {
+        match fetch_and_decode(file).await {
+            Ok(buffer) => return Ok(buffer),
+            Err(err) => failures.push(format!("{file}: {}", err.as_string().unwrap_or_else(|| "decode failed".into()))),
+        }
+    }
+    Err(failures)
+}
+
+fn can_play_type(mime: &str) -> bool {
+    let probe = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.create_element("audio").ok())
+        .and_then(|e| e.dyn_into::<HtmlMediaElement>().ok());
+    // Without a probe element, assume yes and let decoding decide
+    probe.is_none_or(|audio| !audio.can_play_type(mime).is_empty())
+}
+
+async fn fetch_and_decode(path: &str) -> Result<AudioBuffer, JsValue> {
+    let window = web_sys::window().ok_or("no window")?;
+
+    // Aborting rejects the fetch, or the body read if headers already came
+    let controller = AbortController::new()?;
+    let abort = controller.clone();
+    let on_timeout = Closure::once_into_js(move || abort.abort());
+    let timer = window.set_timeout_with_callback_and_timeout_and_arguments_0(on_timeout.unchecked_ref(), FETCH_TIMEOUT_MS)?;
+    let init = RequestInit::new();
+    init.set_signal(Some(&controller.signal()));
+    let download = async {
+        let response: Response = JsFuture::from(window.fetch_with_str_and_init(path, &init)).await?.dyn_into()?;
+        if !response.ok() {
+            return Err(format!("HTTP {}", response.status()).into());
+        }
+        JsFuture::from(response.array_buffer()?).await
+    };
+    let bytes = download.await;
+    window.clear_timeout_with_handle(timer);
+    let bytes = bytes.map_err(|err| if controller.signal().aborted() { JsValue::from_str("timed out") } else { err })?;
+    let decode = with_mixer(|mixer| mixer.ctx.decode_audio_data(bytes.unchecked_ref()))
+        .ok_or("no audio context")??;
+    JsFuture::from(decode).await?.dyn_into()
+}
+
+/// Start a cached buffer on the sound's bus. Returns `false` if the sound
+/// isn't decoded (yet), so the caller can fall back to the synthesizer.
+pub fn play_buffer(sound: Sound, placement: Placement, looping: bool) -> bool {
+    match BUFFERS.with(|b| b.borrow().get(&sound).cloned()) {
+        Some(buffer) => play_decoded(sound, &buffer, placement, looping),
+        None => false,
+    }
+}
+
+/// Play any buffer as a voice of `sound`, sharing its bus and voice limit
+pub fn play_decoded(sound: Sound, buffer: &AudioBuffer, placement: Placement, looping: bool) -> bool {
+    with_mixer(|mixer| {
+        let Ok(source) = mixer.ctx.create_buffer_source() else { return false };
+        source.set_buffer(Some(buffer));
+        source.set_loop(looping);
+        let gain = placement.gain * manifest::entry(sound).volume;
+        if connect_placed(mixer, &source, placement.pan, gain, mixer.bus(sound.bus())).is_err() {
+            return false;
+        }
+
+        let now = mixer.ctx.current_time();
+        VOICES.with(|voices| {
+            let mut voices = voices.borrow_mut();
+            let active = voices.entry(sound).or_default();
+            active.retain(|v| v.ends_at > now);
+            while active.len() >= manifest::entry(sound).max_voices.max(1) {
+                if let Some(oldest) = active.pop_front() {
+                    let _ = AudioScheduledSourceNode::stop(&oldest.source);
+                }
+            }
+            let ends_at = if looping { f64::INFINITY } else { now + buffer.duration() };
+            active.push_back(Voice { source: source.clone(), ends_at });
+        });
+
+        source.start().is_ok()
+    })
+    .unwrap_or(false)
+}
+
+/// source ─> panner ─> gain ─> bus, skipping whichever stages are neutral
+fn connect_placed(mixer: &Mixer, source: &AudioNode, pan: f32, gain: f32, bus: &AudioNode) -> Result<(), JsValue> {
+    let mut output = bus.clone();
+    if gain != 1.0 {
+        let node = mixer.ctx.create_gain()?;
+        node.gain().set_value(gain);
+        node.connect_with_audio_node(&output)?;
+        output = node.into();
+    }
+    if pan != 0.0 {
+        let node = mixer.ctx.create_stereo_panner()?;
+        node.pan().set_value(pan);
+        node.connect_with_audio_node(&output)?;
+        output = node.into();
+    }
+    source.connect_with_audio_node(&output)?;
+    Ok(())
+}
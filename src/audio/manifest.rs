@@ -0,0 +1,97 @@
+//! Audio asset manifest (`manifest.toml`, embedded at compile time)
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use crate::audio::{Bus, Sound};
+
+static MANIFEST: Lazy<Manifest> = Lazy::new(|| {
+    toml::from_str(include_str!("manifest.toml")).expect("audio/manifest.toml is invalid")
+});
+
+#[derive(Deserialize)]
+struct Manifest {
+    sounds: HashMap<Sound, SoundEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SoundEntry {
+    /// Candidate files relative to `public/`, in order of preference
+    pub files: Vec<String>,
+    pub category: Bus,
+    pub volume: f32,
+    pub max_voices: usize,
+}
+
+static FALLBACK: SoundEntry = SoundEntry {
+    files: Vec::new(),
+    category: Bus::Sfx,
+    volume: 1.0,
+    max_voices: 4,
+};
+
+pub fn entry(sound: Sound) -> &'static SoundEntry {
+    MANIFEST.sounds.get(&sound).unwrap_or(&FALLBACK)
+}
+
+/// MIME type to ask `canPlayType` about, from the file extension
+pub fn mime_type(path: &str) -> Option<&'static str> {
+    match path.rsplit('.').next()? {
+        "ogg" => Some("audio/ogg"),
+        "mp3" => Some("audio/mpeg"),
+        "wav" => Some("audio/wav"),
+        "flac" => Some("audio/flac"),
+        _ => None,
+    }
+}
+
+/// The candidates worth fetching, in order: those in a known format that
+/// `can_play_type` accepts the MIME type of
+pub fn playable<'a>(files: &'a [String], can_play_type: impl Fn(&str) -> bool + 'a) -> impl Iterator<Item = &'a str> {
+    files.iter().map(String::as_str).filter(move |file| mime_type(file).is_some_and(&can_play_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn every_sound_has_an_entry() {
+        for sound in Sound::ALL {
+            assert!(MANIFEST.sounds.contains_key(&sound), "{sound:?} missing from manifest.toml");
+        }
+    }
+
+    #[test]
+    fn every_listed_file_exists_under_public() {
+        let public = Path::new(env!("CARGO_MANIFEST_DIR")).join("public");
+        for (sound, entry) in &MANIFEST.sounds {
+            for file in &entry.files {
+                assert!(public.join(file).is_file(), "{sound:?}: public/{file} does not exist");
+                assert!(mime_type(file).is_some(), "{sound:?}: unknown audio format for {file}");
+            }
+        }
+    }
+
+    #[test]
+    fn playable_skips_formats_the_browser_refuses() {
+        let files: Vec<String> = ["a.ogg", "a.mp3", "a.aiff", "a.wav"].map(String::from).into();
+        let everything: Vec<_> = playable(&files, |_| true).collect();
+        assert_eq!(everything, ["a.ogg", "a.mp3", "a.wav"], "unknown formats never play");
+
+        let no_ogg: Vec<_> = playable(&files, |mime| mime != "audio/ogg").collect();
+        assert_eq!(no_ogg, ["a.mp3", "a.wav"]);
+        assert_eq!(playable(&files, |mime| mime == "audio/wav").next(), Some("a.wav"));
+        assert_eq!(playable(&files, |_| false).next(), None);
+
+        // Every sound with assets prefers its smaller Ogg, but still plays without it
+        for sound in Sound::ALL {
+            let files = &entry(sound).files;
+            if !files.is_empty() {
+                assert!(files[0].ends_with(".ogg"), "{sound:?}");
+                assert!(playable(files, |mime| mime != "audio/ogg").next().is_some(), "{sound:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Shared Web Audio context and the mixer graph every sound is routed through
+//!
+//! ```text
+//! music ─┐
+//! sfx   ─┼─> compressor ─> master ─> destination
+//! ui    ─┘
+//! ```
+//!
+//! Browsers cap the number of live `AudioContext`s, so the whole game shares
+//! one, created lazily on first use and kept for the lifetime of the page.
+
+use std::cell::RefCell;
+use wasm_bindgen::JsValue;
+use web_sys::{AudioContext, AudioNode, DynamicsCompressorNode, GainNode};
+use crate::audio::volume;
+
+thread_local! {
+    /// `None` until first use; a failed construction is kept so it is
+    /// only attempted (and reported) once
+    static MIXER: RefCell<Option<Result<Mixer, JsValue>>> = const { RefCell::new(None) };
+}
+
+/// Mixer bus a sound is routed through
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bus {
+    Music,
+    Sfx,
+    Ui,
+}
+
+pub struct Mixer {
+    pub ctx: AudioContext,
+    pub master: GainNode,
+    pub compressor: DynamicsCompressorNode,
+    pub music: GainNode,
+    pub sfx: GainNode,
+    pub ui: GainNode,
+}
+
+impl Mixer {
+    fn new() -> Result<Self, JsValue> {
+        let ctx = AudioContext::new()?;
+
+        let master = ctx.create_gain()?;
+        master.connect_with_audio_node(&ctx.destination())?;
+
+        // Soft-knee limiter in front of the master so a dozen overlapping
+        // explosions squash together instead of clipping.
+        let compressor = ctx.create_dynamics_compressor()?;
+        compressor.threshold().set_value(-12.0);
+        compressor.knee().set_value(10.0);
+        compressor.ratio().set_value(8.0);
+        compressor.attack().set_value(0.003);
+        compressor.release().set_value(0.25);
+        compressor.connect_with_audio_node(&master)?;
+
+        let music = create_bus(&ctx, &compressor)?;
+        let sfx = create_bus(&ctx, &compressor)?;
+        let ui = create_bus(&ctx, &compressor)?;
+
+        let mixer = Mixer { ctx, master, compressor, music, sfx, ui };
+        volume::apply(&mixer);
+        Ok(mixer)
+    }
+
+    /// Input node of a bus; sources connect here
+    pub fn bus(&self, bus: Bus) -> &AudioNode {
+        match bus {
+            Bus::Music => &self.music,
+            Bus::Sfx => &self.sfx,
+            Bus::Ui => &self.ui,
+        }
+    }
+}
+
+fn create_bus(ctx: &AudioContext, output: &AudioNode) -> Result<GainNode, JsValue> {
+    let gain = ctx.create_gain()?;
+    gain.connect_with_audio_node(output)?;
+    Ok(gain)
+}
+
+/// Run `f` against the shared mixer, creating it on first use.
+/// Returns `None` when Web Audio is unavailable.
+pub fn with_mixer<R>(f: impl FnOnce(&Mixer) -> R) -> Option<R> {
+    // No Web Audio off the web; headless runs (tests) are silent
+    if !cfg!(target_arch = "wasm32") {
+        return None;
+    }
+    MIXER.with(|cell| {
+        if cell.borrow().is_none() {
+            let mixer = Mixer::new();
+            if let Err(err) = &mixer {
+                log::error!("Web Audio unavailable: {:?}", err);
+            }
+            *cell.borrow_mut() = Some(mixer);
+        }
+        cell.borrow().as_ref()?.as_ref().ok().map(f)
+    })
+}
@@ -0,0 +1,649 @@
+//! Step-sequencer music engine for the generated synthwave track
+//!
+//! A song is a set of tracks, each with one 16-step pattern per bar. A
+//! look-ahead scheduler wakes every `TICK_MS` and queues every step that
+//! falls inside the next `LOOKAHEAD_SECS` on the audio clock, so timing is
+//! sample-accurate no matter how jittery the JS timer is. Every note is a
+//! short-lived node graph with an explicit stop time; nothing is left
+//! running when the sequencer stops.
+//!
+//! Tracks belong to intensity layers that are crossfaded from game state
+//! (see [`MusicMood`]):
+//!
+//! ```text
+//! core ───────┐
+//! percussion ─┼─> layer gains ─> tension filter ─> output ─> music bus
+//! fast arp ───┤
+//! tension ────┘                       stingers ────────────> music bus
+//! ```
+
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    AudioBuffer, AudioBufferSourceNode, AudioContext, AudioNode, AudioScheduledSourceNode,
+    BiquadFilterType, GainNode, OscillatorType,
+};
+
+use crate::audio::mixer::{with_mixer, Bus};
+use crate::rng::Rng;
+
+const SYNTHWAVE_BPM: f64 = 100.0;
+const STEPS_PER_BAR: usize = 16;
+const STEPS_PER_BEAT: f64 = 4.0;
+const TICK_MS: i32 = 25;
+const LOOKAHEAD_SECS: f64 = 0.1;
+const FADE_SECS: f64 = 0.08;
+/// Time constant for layer crossfades
+const CROSSFADE_SECS: f64 = 0.6;
+/// Low-pass cutoff while the music is calm / tense
+const OPEN_CUTOFF: f32 = 18_000.0;
+const TENSE_CUTOFF: f32 = 1_100.0;
+/// Combo at which every intensity layer is fully in
+const MAX_INTENSITY_COMBO: f32 = 10.0;
+/// Planet health at or below which the music turns tense
+const TENSE_HEALTH: i32 = 30;
+
+thread_local! {
+    static SEQUENCER: RefCell<Option<Sequencer>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instrument {
+    Bass,
+    Lead,
+    Pad,
+    Kick,
+    Snare,
+    HiHat,
+}
+
+/// Crossfadeable group of tracks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    /// Always playing
+    Core,
+    /// Extra drums, in from a small combo
+    Percussion,
+    /// Double-time arpeggio, in from a big combo
+    FastArp,
+    /// Dissonant pulse that comes with the tense filter
+    Tension,
+}
+
+impl Layer {
+    const ALL: [Layer; 4] = [Layer::Core, Layer::Percussion, Layer::FastArp, Layer::Tension];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// What the game wants the music to express right now
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MusicMood {
+    /// 0..1, drives the percussion and fast arpeggio layers
+    pub intensity: f32,
+    /// Low planet health: filter everything down and add the tension layer
+    pub tense: bool,
+}
+
+impl Default for MusicMood {
+    fn default() -> Self {
+        MusicMood { intensity: 0.0, tense: false }
+    }
+}
+
+impl MusicMood {
+    pub fn from_game(combo: u32, planet_health: i32) -> Self {
+        // Quantize so the mood only changes when a layer would audibly move
+        let intensity = (combo as f32 / MAX_INTENSITY_COMBO).min(1.0);
+        MusicMood {
+            intensity: (intensity * 10.0).round() / 10.0,
+            tense: planet_health <= TENSE_HEALTH,
+        }
+    }
+
+    fn layer_level(&self, layer: Layer) -> f32 {
+        match layer {
+            Layer::Core => 1.0,
+            Layer::Percussion => (self.intensity * 2.0).min(1.0),
+            Layer::FastArp => ((self.intensity - 0.5) * 2.0).clamp(0.0, 1.0),
+            Layer::Tension => if self.tense { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+/// Short phrases played over (or instead of) the loop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stinger {
+    WaveClear,
+    GameOver,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct NoteEvent {
+    pub step: usize,
+    /// MIDI note number; ignored by the drums
+    pub note: u8,
+    pub length: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Pattern {
+    pub events: Vec<NoteEvent>,
+}
+
+impl Pattern {
+    fn notes_at(&self, step: usize) -> impl Iterator<Item = &NoteEvent> {
+        self.events.iter().filter(move |e| e.step == step)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Track {
+    pub instrument: Instrument,
+    pub layer: Layer,
+    pub level: f32,
+    /// One pattern per bar of the song
+    pub bars: Vec<Pattern>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Song {
+    pub bpm: f64,
+    pub tracks: Vec<Track>,
+}
+
+impl Song {
+    fn bar_count(&self) -> usize {
+        self.tracks.iter().map(|t| t.bars.len()).max().unwrap_or(1).max(1)
+    }
+
+    fn step_secs(&self) -> f64 {
+        60.0 / self.bpm / STEPS_PER_BEAT
+    }
+}
+
+struct Sequencer {
+    ctx: AudioContext,
+    song: Song,
+    output: GainNode,
+    tension_filter: web_sys::BiquadFilterNode,
+    layer_gains: Vec<GainNode>,
+    track_outputs: Vec<GainNode>,
+    noise: AudioBuffer,
+    mood: MusicMood,
+    step: usize,
+    next_step_time: f64,
+    interval_id: i32,
+    _tick: Closure<dyn FnMut()>,
+}
+
+impl Sequencer {
+    fn set_mood(&mut self, mood: MusicMood) {
+        if mood == self.mood {
+            return;
+        }
+        self.mood = mood;
+
+        let now = self.ctx.current_time();
+        for layer in Layer::ALL {
+            let gain = self.layer_gains[layer.index()].gain();
+            let _ = gain.set_target_at_time(mood.layer_level(layer), now, CROSSFADE_SECS / 3.0);
+        }
+        let cutoff = if mood.tense { TENSE_CUTOFF } else { OPEN_CUTOFF };
+        let _ = self.tension_filter.frequency().set_target_at_time(cutoff, now, CROSSFADE_SECS / 3.0);
+    }
+
+    /// Audio time of the next beat, so stingers land in time with the loop
+    fn next_beat_time(&self) -> f64 {
+        let steps_to_beat = (STEPS_PER_BEAT as usize - self.step % STEPS_PER_BEAT as usize) % STEPS_PER_BEAT as usize;
+        self.next_step_time + steps_to_beat as f64 * self.song.step_secs()
+    }
+
+    /// Queue every step that starts before the look-ahead horizon
+    fn schedule_ahead(&mut self) {
+        let now = self.ctx.current_time();
+        let horizon = now + LOOKAHEAD_SECS;
+        let step_secs = self.song.step_secs();
+        let bars = self.song.bar_count();
+
+        // A throttled timer (background tab) or a stalled main thread leaves
+        // steps in the past; skip them rather than play them all at once
+        let missed = steps_behind(self.next_step_time, now, step_secs);
+        self.step += missed;
+        self.next_step_time += missed as f64 * step_secs;
+
+        while self.next_step_time < horizon {
+            let bar = (self.step / STEPS_PER_BAR) % bars;
+            let step_in_bar = self.step % STEPS_PER_BAR;
+
+            for (track, output) in self.song.tracks.iter().zip(&self.track_outputs) {
+                let Some(pattern) = track.bars.get(bar % track.bars.len().max(1)) else { continue };
+                for event in pattern.notes_at(step_in_bar) {
+                    let voice = Voice {
+                        ctx: &self.ctx,
+                        output,
+                        noise: Some(&self.noise),
+                        when: self.next_step_time,
+                        duration: event.length as f64 * step_secs,
+                    };
+                    let _ = voice.play(track.instrument, midi_to_freq(event.note));
+                }
+            }
+
+            self.step += 1;
+            self.next_step_time += step_secs;
+        }
+    }
+
+    fn shutdown(self) {
+        if let Some(window) = web_sys::window() {
+            window.clear_interval_with_handle(self.interval_id);
+        }
+        // Notes already queued keep their stop times; fade the output so they
+        // don't tail on, and let the nodes be collected
+        let now = self.ctx.current_time();
+        let _ = self.output.gain().set_target_at_time(0.0, now, FADE_SECS / 3.0);
+    }
+}
+
+/// Steps to skip so the next one starts no earlier than `now`
+fn steps_behind(next_step_time: f64, now: f64, step_secs: f64) -> usize {
+    if next_step_time >= now {
+        return 0;
+    }
+    ((now - next_step_time) / step_secs).ceil() as usize
+}
+
+/// One scheduled note
+struct Voice<'a> {
+    ctx: &'a AudioContext,
+    output: &'a AudioNode,
+    noise: Option<&'a AudioBuffer>,
+    when: f64,
+    duration: f64,
+}
+
+impl Voice<'_> {
+    fn play(&self, instrument: Instrument, freq: f32) -> Result<(), JsValue> {
+        match instrument {
+            Instrument::Bass => {
+                let filter = self.filter(BiquadFilterType::Lowpass, 900.0, 1.0)?;
+                filter.frequency().exponential_ramp_to_value_at_time(250.0, self.when + self.duration)?;
+                let gain = self.envelope(0.35, 0.005, self.duration)?;
+                self.oscillator(OscillatorType::Sawtooth, freq, &filter)?;
+                filter.connect_with_audio_node(&gain)?;
+            }
+            Instrument::Lead => {
+                let filter = self.filter(BiquadFilterType::Lowpass, 2400.0, 2.0)?;
+                let gain = self.pluck(0.12, self.duration)?;
+                self.oscillator(OscillatorType::Square, freq, &filter)?;
+                filter.connect_with_audio_node(&gain)?;
+            }
+            Instrument::Pad => {
+                let filter = self.filter(BiquadFilterType::Lowpass, 900.0, 0.5)?;
+                let gain = self.envelope(0.06, self.duration * 0.3, self.duration)?;
+                // Two slightly detuned voices for width
+                self.oscillator(OscillatorType::Triangle, freq, &filter)?;
+                self.oscillator(OscillatorType::Triangle, freq * 1.004, &filter)?;
+                filter.connect_with_audio_node(&gain)?;
+            }
+            Instrument::Kick => {
+                let gain = self.pluck(0.9, 0.3)?;
+                let osc = self.ctx.create_oscillator()?;
+                osc.set_type(OscillatorType::Sine);
+                osc.frequency().set_value_at_time(150.0, self.when)?;
+                osc.frequency().exponential_ramp_to_value_at_time(45.0, self.when + 0.12)?;
+                osc.connect_with_audio_node(&gain)?;
+                osc.start_with_when(self.when)?;
+                osc.stop_with_when(self.when + 0.3)?;
+            }
+            Instrument::Snare => {
+                let filter = self.filter(BiquadFilterType::Highpass, 1500.0, 0.7)?;
+                let gain = self.pluck(0.35, 0.15)?;
+                self.noise_burst(&filter, 0.15)?;
+                filter.connect_with_audio_node(&gain)?;
+            }
+            Instrument::HiHat => {
+                let filter = self.filter(BiquadFilterType::Highpass, 7000.0, 0.7)?;
+                let gain = self.pluck(0.12, 0.05)?;
+                self.noise_burst(&filter, 0.05)?;
+                filter.connect_with_audio_node(&gain)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Attack/hold/release gain into the track output
+    fn envelope(&self, level: f32, attack: f64, length: f64) -> Result<GainNode, JsValue> {
+        let gain = self.ctx.create_gain()?;
+        let release = (length * 0.2).min(0.15);
+        gain.gain().set_value_at_time(0.0, self.when)?;
+        gain.gain().linear_ramp_to_value_at_time(level, self.when + attack)?;
+        gain.gain().set_value_at_time(level, self.when + length - release)?;
+        gain.gain().linear_ramp_to_value_at_time(0.0, self.when + length)?;
+        gain.connect_with_audio_node(self.output)?;
+        Ok(gain)
+    }
+
+    /// Instant attack, exponential decay
+    fn pluck(&self, level: f32, length: f64) -> Result<GainNode, JsValue> {
+        let gain = self.ctx.create_gain()?;
+        gain.gain().set_value_at_time(level, self.when)?;
+        gain.gain().exponential_ramp_to_value_at_time(0.001, self.when + length)?;
+        gain.connect_with_audio_node(self.output)?;
+        Ok(gain)
+    }
+
+    fn filter(&self, kind: BiquadFilterType, cutoff: f32, q: f32) -> Result<web_sys::BiquadFilterNode, JsValue> {
+        let filter = self.ctx.create_biquad_filter()?;
+        filter.set_type(kind);
+        filter.frequency().set_value_at_time(cutoff, self.when)?;
+        filter.q().set_value(q);
+        Ok(filter)
+    }
+
+    fn oscillator(&self, kind: OscillatorType, freq: f32, output: &AudioNode) -> Result<(), JsValue> {
+        let osc = self.ctx.create_oscillator()?;
+        osc.set_type(kind);
+        osc.frequency().set_value_at_time(freq, self.when)?;
+        osc.connect_with_audio_node(output)?;
+        osc.start_with_when(self.when)?;
+        osc.stop_with_when(self.when + self.duration)?;
+        Ok(())
+    }
+
+    fn noise_burst(&self, output: &AudioNode, length: f64) -> Result<(), JsValue> {
+        let Some(noise) = self.noise else { return Ok(()) };
+        let source: AudioBufferSourceNode = self.ctx.create_buffer_source()?;
+        source.set_buffer(Some(noise));
+        source.connect_with_audio_node(output)?;
+        AudioScheduledSourceNode::start_with_when(&source, self.when)?;
+        AudioScheduledSourceNode::stop_with_when(&source, self.when + length)?;
+        Ok(())
+    }
+}
+
+fn midi_to_freq(note: u8) -> f32 {
+    440.0 * ((note as f32 - 69.0) / 12.0).exp2()
+}
+
+/// One second of white noise shared by every drum hit
+fn noise_buffer(ctx: &AudioContext) -> Result<AudioBuffer, JsValue> {
+    let rate = ctx.sample_rate();
+    let mut rng = Rng::new(0x5EED);
+    let samples: Vec<f32> = (0..rate as usize).map(|_| rng.signed() as f32).collect();
+    let buffer = ctx.create_buffer(1, samples.len() as u32, rate)?;
+    buffer.copy_to_channel(&samples, 0)?;
+    Ok(buffer)
+}
+
+pub fn is_playing() -> bool {
+    SEQUENCER.with(|s| s.borrow().is_some())
+}
+
+/// Start the sequencer on the music bus. No-op if it's already running.
+pub fn start(song: Song) {
+    if is_playing() {
+        return;
+    }
+
+    let started = with_mixer(|mixer| -> Result<Sequencer, JsValue> {
+        let ctx = mixer.ctx.clone();
+        let mood = MusicMood::default();
+        let output = ctx.create_gain()?;
+        output.connect_with_audio_node(mixer.bus(Bus::Music))?;
+
+        let tension_filter = ctx.create_biquad_filter()?;
+        tension_filter.set_type(BiquadFilterType::Lowpass);
+        tension_filter.frequency().set_value(OPEN_CUTOFF);
+        tension_filter.q().set_value(4.0);
+        tension_filter.connect_with_audio_node(&output)?;
+
+        let layer_gains = Layer::ALL
+            .iter()
+            .map(|&layer| {
+                let gain = ctx.create_gain()?;
+                gain.gain().set_value(mood.layer_level(layer));
+                gain.connect_with_audio_node(&tension_filter)?;
+                Ok(gain)
+            })
+            .collect::<Result<Vec<_>, JsValue>>()?;
+
+        let track_outputs = song
+            .tracks
+            .iter()
+            .map(|track| {
+                let gain = ctx.create_gain()?;
+                gain.gain().set_value(track.level);
+                gain.connect_with_audio_node(&layer_gains[track.layer.index()])?;
+                Ok(gain)
+            })
+            .collect::<Result<Vec<_>, JsValue>>()?;
+
+        let tick = Closure::wrap(Box::new(|| {
+            SEQUENCER.with(|s| {
+                if let Some(sequencer) = s.borrow_mut().as_mut() {
+                    sequencer.schedule_ahead();
+                }
+            });
+        }) as Box<dyn FnMut()>);
+
+        let interval_id = web_sys::window()
+            .ok_or("no window")?
+            .set_interval_with_callback_and_timeout_and_arguments_0(tick.as_ref().unchecked_ref(), TICK_MS)?;
+
+        Ok(Sequencer {
+            noise: noise_buffer(&ctx)?,
+            next_step_time: ctx.current_time() + 0.05,
+            ctx,
+            song,
+            output,
+            tension_filter,
+            layer_gains,
+            track_outputs,
+            mood,
+            step: 0,
+            interval_id,
+            _tick: tick,
+        })
+    });
+
+    match started {
+        Some(Ok(mut sequencer)) => {
+            sequencer.schedule_ahead();
+            SEQUENCER.with(|s| *s.borrow_mut() = Some(sequencer));
+        }
+        Some(Err(_)) | None => {
+            log::error!("Could not start the music sequencer");
+        }
+    }
+}
+
+/// Stop scheduling and fade out whatever is already queued
+pub fn stop() {
+    if let Some(sequencer) = SEQUENCER.with(|s| s.borrow_mut().take()) {
+        sequencer.shutdown();
+    }
+}
+
+/// Crossfade the intensity layers towards `mood`
+pub fn set_mood(mood: MusicMood) {
+    SEQUENCER.with(|s| {
+        if let Some(sequencer) = s.borrow_mut().as_mut() {
+            sequencer.set_mood(mood);
+        }
+    });
+}
+
+/// Play a stinger on the next beat. The game-over stinger also fades the loop out.
+pub fn stinger(kind: Stinger) {
+    let beat = SEQUENCER.with(|s| s.borrow().as_ref().map(Sequencer::next_beat_time));
+
+    with_mixer(|mixer| -> Result<(), JsValue> {
+        let output = mixer.ctx.create_gain()?;
+        output.connect_with_audio_node(mixer.bus(Bus::Music))?;
+        let start = beat.unwrap_or_else(|| mixer.ctx.current_time());
+
+        // (MIDI note, start in 16ths, length in 16ths) at the loop's tempo
+        let phrase: &[(u8, usize, usize)] = match kind {
+            Stinger::WaveClear => &[(69, 0, 1), (73, 1, 1), (76, 2, 1), (81, 3, 4)],
+            Stinger::GameOver => &[(69, 0, 3), (65, 3, 3), (62, 6, 3), (57, 9, 10)],
+        };
+        let step_secs = 60.0 / SYNTHWAVE_BPM / STEPS_PER_BEAT;
+        for &(note, at, length) in phrase {
+            let voice = Voice {
+                ctx: &mixer.ctx,
+                output: &output,
+                noise: None,
+                when: start + at as f64 * step_secs,
+                duration: length as f64 * step_secs,
+            };
+            voice.play(Instrument::Lead, midi_to_freq(note))?;
+            voice.play(Instrument::Pad, midi_to_freq(note - 12))?;
+        }
+        Ok(())
+    });
+
+    if kind == Stinger::GameOver {
+        stop();
+    }
+}
+
+/// The generated synthwave loop: Am - F - C - G at 100 BPM
+pub fn synthwave() -> Song {
+    // (bass root, arpeggio chord tones) per bar, as MIDI notes
+    const PROGRESSION: [(u8, [u8; 4]); 4] = [
+        (33, [57, 60, 64, 69]), // Am
+        (29, [53, 57, 60, 65]), // F
+        (36, [60, 64, 67, 72]), // C
+        (31, [55, 59, 62, 67]), // G
+    ];
+    const ARP_ORDER: [usize; 8] = [0, 1, 2, 3, 2, 1, 2, 3];
+
+    let bars = |f: &dyn Fn(u8, [u8; 4]) -> Vec<NoteEvent>| -> Vec<Pattern> {
+        PROGRESSION
+            .iter()
+            .map(|&(root, chord)| Pattern { events: f(root, chord) })
+            .collect()
+    };
+    let note = |step, note, length| NoteEvent { step, note, length };
+    let hits = |steps: &[usize]| Pattern { events: steps.iter().map(|&s| note(s, 0, 1)).collect() };
+
+    Song {
+        bpm: SYNTHWAVE_BPM,
+        tracks: vec![
+            Track {
+                instrument: Instrument::Bass,
+                layer: Layer::Core,
+                level: 0.8,
+                // Driving eighths with an octave jump on the off-beat
+                bars: bars(&|root, _| {
+                    (0..STEPS_PER_BAR)
+                        .step_by(2)
+                        .map(|s| note(s, if s % 8 == 6 { root + 12 } else { root }, 2))
+                        .collect()
+                }),
+            },
+            Track {
+                instrument: Instrument::Lead,
+                layer: Layer::Core,
+                level: 0.6,
+                // Eighth-note arpeggio
+                bars: bars(&|_, chord| {
+                    (0..STEPS_PER_BAR)
+                        .step_by(2)
+                        .map(|s| note(s, chord[ARP_ORDER[s / 2]], 2))
+                        .collect()
+                }),
+            },
+            Track {
+                instrument: Instrument::Lead,
+                layer: Layer::FastArp,
+                level: 0.45,
+                // Sixteenths an octave up
+                bars: bars(&|_, chord| {
+                    (0..STEPS_PER_BAR)
+                        .map(|s| note(s, chord[ARP_ORDER[s % ARP_ORDER.len()]] + 12, 1))
+                        .collect()
+                }),
+            },
+            Track {
+                instrument: Instrument::Pad,
+                layer: Layer::Core,
+                level: 0.7,
+                bars: bars(&|_, chord| chord[..3].iter().map(|&n| note(0, n - 12, STEPS_PER_BAR)).collect()),
+            },
+            Track {
+                instrument: Instrument::Bass,
+                layer: Layer::Tension,
+                level: 0.5,
+                // Pulsing tritone over the root
+                bars: bars(&|root, _| (0..STEPS_PER_BAR).step_by(4).map(|s| note(s, root + 18, 3)).collect()),
+            },
+            Track { instrument: Instrument::Kick, layer: Layer::Core, level: 0.8, bars: vec![hits(&[0, 4, 8, 12])] },
+            Track { instrument: Instrument::Snare, layer: Layer::Core, level: 0.6, bars: vec![hits(&[4, 12])] },
+            Track { instrument: Instrument::HiHat, layer: Layer::Core, level: 0.5, bars: vec![hits(&[2, 6, 10, 14])] },
+            Track {
+                instrument: Instrument::HiHat,
+                layer: Layer::Percussion,
+                level: 0.4,
+                bars: vec![hits(&[1, 3, 5, 7, 9, 11, 13, 15])],
+            },
+            Track {
+                instrument: Instrument::Kick,
+                layer: Layer::Percussion,
+                level: 0.5,
+                bars: vec![hits(&[7, 10, 14])],
+            },
+            Track {
+                instrument: Instrument::Snare,
+                layer: Layer::Percussion,
+                level: 0.3,
+                bars: vec![hits(&[15])],
+            },
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_stall_skips_to_the_next_step_boundary() {
+        assert_eq!(steps_behind(10.0, 9.0, 0.125), 0);
+        assert_eq!(steps_behind(10.0, 10.0, 0.125), 0);
+        // 1.05 s late: eight and a bit steps missed, resume on the ninth
+        let missed = steps_behind(10.0, 11.05, 0.125);
+        assert_eq!(missed, 9);
+        assert!(10.0 + missed as f64 * 0.125 >= 11.05);
+    }
+
+    #[test]
+    fn mood_follows_combo_and_planet_health() {
+        assert_eq!(MusicMood::from_game(0, 100), MusicMood::default());
+        assert_eq!(MusicMood::from_game(5, 100).intensity, 0.5);
+        assert_eq!(MusicMood::from_game(40, 100).intensity, 1.0, "capped at a full combo");
+        // Quantized to tenths, so a combo of 3 isn't 0.3000001
+        assert_eq!(MusicMood::from_game(3, 100).intensity, 0.3);
+        assert!(!MusicMood::from_game(0, TENSE_HEALTH + 1).tense);
+        assert!(MusicMood::from_game(0, TENSE_HEALTH).tense);
+        assert!(MusicMood::from_game(0, 0).tense);
+    }
+
+    #[test]
+    fn layers_fade_in_with_the_mood() {
+        let levels = |intensity, tense| Layer::ALL.map(|layer| MusicMood { intensity, tense }.layer_level(layer));
+        // Core, percussion, fast arp, tension
+        assert_eq!(levels(0.0, false), [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(levels(0.25, false), [1.0, 0.5, 0.0, 0.0]);
+        assert_eq!(levels(0.5, false), [1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(levels(0.75, false), [1.0, 1.0, 0.5, 0.0]);
+        assert_eq!(levels(1.0, false), [1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(levels(0.0, true), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(levels(1.0, true), [1.0, 1.0, 1.0, 1.0]);
+    }
+}
@@ -0,0 +1,43 @@
+//! Desktop audio output through rodio (`--features native`)
+//!
+//! There is no asset pipeline on this side yet; every sound is rendered by
+//! the synthesizer into PCM and handed to the default output device.
+
+use rodio::buffer::SamplesBuffer;
+use rodio::{OutputStream, OutputStreamHandle};
+use crate::audio::mixer::Bus;
+use crate::audio::{manifest, synth, volume, Placement, Sound};
+
+const SAMPLE_RATE: u32 = 44_100;
+
+thread_local! {
+    // The stream must outlive every sound played through its handle
+    static OUTPUT: Option<(OutputStream, OutputStreamHandle)> = OutputStream::try_default().ok();
+}
+
+/// Play mono PCM on the default device, panned into stereo.
+/// Returns `false` if there is no device.
+pub fn play_samples(samples: &[f32], sample_rate: u32, placement: Placement) -> bool {
+    let (left, right) = placement.channel_gains();
+    let stereo: Vec<f32> = samples.iter().flat_map(|&s| [s * left, s * right]).collect();
+    OUTPUT.with(|output| match output {
+        Some((_, handle)) => handle.play_raw(SamplesBuffer::new(2, sample_rate, stereo)).is_ok(),
+        None => false,
+    })
+}
+
+/// Render and play `sound` at the player's volume settings. A muted sound
+/// counts as played, since there is nothing else to fall back to.
+pub fn play(sound: Sound, placement: Placement) -> bool {
+    let levels = volume::settings();
+    if levels.muted {
+        return true;
+    }
+    let bus = match sound.bus() {
+        Bus::Music => levels.music,
+        Bus::Sfx | Bus::Ui => levels.sfx,
+    };
+    let gain = placement.gain * manifest::entry(sound).volume * levels.master * bus;
+    let placement = Placement { gain, ..placement };
+    play_samples(&synth::render_sound(sound, SAMPLE_RATE as f32), SAMPLE_RATE, placement)
+}
@@ -0,0 +1,80 @@
+//! Where a sound sits in the stereo field
+//!
+//! Sounds are panned by their x position relative to the planet at the
+//! centre of the screen and attenuated by how far off-screen they are, so
+//! the player can hear which side a threat is hitting from.
+
+use crate::constants::*;
+
+/// Distance past the screen edge at which a sound is at half volume
+const OFFSCREEN_HALF_GAIN_DIST: f64 = 150.0;
+/// Never pan fully hard; a touch of the other side sounds more natural
+const MAX_PAN: f64 = 0.85;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    /// -1 (left) to 1 (right)
+    pub pan: f32,
+    /// Distance attenuation, 0..1
+    pub gain: f32,
+}
+
+impl Placement {
+    pub const CENTER: Placement = Placement { pan: 0.0, gain: 1.0 };
+
+    /// Placement of a sound emitted at world position `(x, y)`
+    pub fn at(x: f64, y: f64) -> Self {
+        let half_width = WORLD_WIDTH / 2.0;
+        let pan = ((x - half_width) / half_width).clamp(-1.0, 1.0) * MAX_PAN;
+
+        let outside_x = (-x).max(x - WORLD_WIDTH).max(0.0);
+        let outside_y = (-y).max(y - WORLD_HEIGHT).max(0.0);
+        let outside = outside_x.hypot(outside_y);
+        let gain = 1.0 / (1.0 + outside / OFFSCREEN_HALF_GAIN_DIST);
+
+        Placement { pan: pan as f32, gain: gain as f32 }
+    }
+
+    /// Equal-power left/right gains, for backends without a panner node
+    pub fn channel_gains(&self) -> (f32, f32) {
+        let angle = (self.pan + 1.0) * std::f32::consts::FRAC_PI_4;
+        (angle.cos() * self.gain, angle.sin() * self.gain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pans_across_the_screen() {
+        let (mid_x, mid_y) = (WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        assert_eq!(Placement::at(mid_x, mid_y), Placement::CENTER);
+        assert_eq!(Placement::at(0.0, mid_y).pan, -MAX_PAN as f32);
+        assert_eq!(Placement::at(WORLD_WIDTH, mid_y).pan, MAX_PAN as f32);
+        // Past the edge it stays at the limit
+        assert_eq!(Placement::at(-500.0, mid_y).pan, -MAX_PAN as f32);
+        assert_eq!(Placement::at(mid_x, -500.0).pan, 0.0);
+    }
+
+    #[test]
+    fn fades_with_distance_off_screen() {
+        let mid_y = WORLD_HEIGHT / 2.0;
+        assert_eq!(Placement::at(WORLD_WIDTH, mid_y).gain, 1.0, "full volume anywhere on screen");
+        assert_eq!(Placement::at(-OFFSCREEN_HALF_GAIN_DIST, mid_y).gain, 0.5);
+        assert_eq!(Placement::at(WORLD_WIDTH / 2.0, WORLD_HEIGHT + OFFSCREEN_HALF_GAIN_DIST).gain, 0.5);
+        assert!(Placement::at(WORLD_WIDTH + 3.0 * OFFSCREEN_HALF_GAIN_DIST, mid_y).gain < 0.5);
+    }
+
+    #[test]
+    fn channel_gains_keep_equal_power() {
+        for pan in [-1.0, -MAX_PAN as f32, -0.3, 0.0, 0.5, 1.0] {
+            let (left, right) = Placement { pan, gain: 0.5 }.channel_gains();
+            assert!((left * left + right * right - 0.25).abs() < 1e-6, "pan {pan}");
+        }
+        let (left, right) = Placement::CENTER.channel_gains();
+        assert!((left - right).abs() < 1e-6);
+        let (left, right) = Placement { pan: -1.0, gain: 1.0 }.channel_gains();
+        assert!((left - 1.0).abs() < 1e-6 && right.abs() < 1e-6);
+    }
+}
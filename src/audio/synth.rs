@@ -0,0 +1,292 @@
+//! sfxr-style procedural sound effects
+//!
+//! A handful of parameters (waveform, ADSR-ish envelope, pitch slide,
+//! vibrato, noise, one-pole low-pass) rendered offline into mono `f32` PCM.
+//! The output is backend-agnostic: the web path copies it into an
+//! `AudioBuffer`, the native path hands it to rodio.
+
+use std::cell::RefCell;
+use std::f32::consts::TAU;
+use crate::audio::Sound;
+use crate::rng::Rng;
+
+thread_local! {
+    static VARIATION_RNG: RefCell<Rng> = RefCell::new(Rng::from_entropy());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Waveform {
+    Square,
+    Sawtooth,
+    Sine,
+    Triangle,
+    Noise,
+}
+
+#[derive(Clone, Debug)]
+pub struct SfxParams {
+    pub waveform: Waveform,
+    /// Square wave duty cycle, 0..1
+    pub duty: f32,
+    /// Starting pitch in Hz
+    pub base_freq: f32,
+    /// Pitch slide in octaves per second (negative drops)
+    pub freq_slide: f32,
+    /// The sound stops early once the slide falls below this pitch
+    pub min_freq: f32,
+    /// Vibrato depth as a fraction of the pitch
+    pub vibrato_depth: f32,
+    /// Vibrato rate in Hz
+    pub vibrato_speed: f32,
+    /// Envelope stages in seconds
+    pub attack: f32,
+    pub sustain: f32,
+    pub decay: f32,
+    /// Extra level at the start of sustain that fades out over the stage
+    pub punch: f32,
+    /// White noise mixed over the waveform, 0..1
+    pub noise: f32,
+    /// Low-pass cutoff in Hz; `None` leaves the signal unfiltered
+    pub lowpass: Option<f32>,
+    /// Cutoff slide in octaves per second
+    pub lowpass_sweep: f32,
+    /// Output gain
+    pub volume: f32,
+}
+
+impl Default for SfxParams {
+    fn default() -> Self {
+        SfxParams {
+            waveform: Waveform::Square,
+            duty: 0.5,
+            base_freq: 440.0,
+            freq_slide: 0.0,
+            min_freq: 20.0,
+            vibrato_depth: 0.0,
+            vibrato_speed: 0.0,
+            attack: 0.0,
+            sustain: 0.1,
+            decay: 0.1,
+            punch: 0.0,
+            noise: 0.0,
+            lowpass: None,
+            lowpass_sweep: 0.0,
+            volume: 0.3,
+        }
+    }
+}
+
+impl SfxParams {
+    pub fn preset(sound: Sound) -> Self {
+        match sound {
+            // Laser "pew": bright square dropping two octaves
+            Sound::Shoot => SfxParams {
+                waveform: Waveform::Square,
+                duty: 0.3,
+                base_freq: 1200.0,
+                freq_slide: -13.0,
+                min_freq: 150.0,
+                attack: 0.005,
+                sustain: 0.04,
+                decay: 0.11,
+                punch: 0.3,
+                lowpass: Some(9000.0),
+                volume: 0.25,
+                ..Default::default()
+            },
+            // Low rumbling burst that darkens as it fades
+            Sound::Explosion => SfxParams {
+                waveform: Waveform::Noise,
+                base_freq: 900.0,
+                freq_slide: -1.5,
+                sustain: 0.08,
+                decay: 0.5,
+                punch: 0.6,
+                lowpass: Some(3000.0),
+                lowpass_sweep: -2.5,
+                volume: 0.5,
+                ..Default::default()
+            },
+            // Heavy, wobbling impact on the planet
+            Sound::PlanetHit => SfxParams {
+                waveform: Waveform::Sawtooth,
+                base_freq: 110.0,
+                freq_slide: -1.0,
+                vibrato_depth: 0.1,
+                vibrato_speed: 12.0,
+                sustain: 0.15,
+                decay: 0.35,
+                punch: 0.4,
+                noise: 0.4,
+                lowpass: Some(1200.0),
+                volume: 0.45,
+                ..Default::default()
+            },
+            // Soft pad swell, only heard if the music engine is unavailable
+            Sound::Background => SfxParams {
+                waveform: Waveform::Triangle,
+                base_freq: 110.0,
+                vibrato_depth: 0.02,
+                vibrato_speed: 5.0,
+                attack: 0.5,
+                sustain: 1.0,
+                decay: 1.0,
+                lowpass: Some(800.0),
+                volume: 0.2,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// How much each sound wanders between plays
+    fn variation(sound: Sound) -> f32 {
+        match sound {
+            Sound::Shoot => 0.08,
+            Sound::Explosion => 0.2,
+            Sound::PlanetHit => 0.1,
+            Sound::Background => 0.0,
+        }
+    }
+
+    /// Randomly nudge pitch, slide, length and tone by up to `amount`
+    /// (a fraction) so repeated plays don't sound identical
+    pub fn varied(&self, amount: f32, rng: &mut Rng) -> Self {
+        let mut jitter = |scale: f32| 1.0 + amount * scale * rng.signed() as f32;
+        let mut params = self.clone();
+        params.base_freq *= jitter(1.0);
+        params.freq_slide *= jitter(1.0);
+        params.sustain *= jitter(0.5);
+        params.decay *= jitter(1.0);
+        params.punch *= jitter(1.0);
+        params.vibrato_speed *= jitter(1.0);
+        params.lowpass = params.lowpass.map(|cutoff| cutoff * jitter(1.0));
+        params
+    }
+
+    /// Preset for `sound` with that sound's per-play variation applied
+    pub fn for_play(sound: Sound) -> Self {
+        let preset = Self::preset(sound);
+        VARIATION_RNG.with(|rng| preset.varied(Self::variation(sound), &mut rng.borrow_mut()))
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.attack + self.sustain + self.decay
+    }
+
+    fn envelope(&self, t: f32) -> f32 {
+        if t < self.attack {
+            t / self.attack
+        } else if t < self.attack + self.sustain {
+            let progress = (t - self.attack) / self.sustain;
+            1.0 + self.punch * (1.0 - progress)
+        } else {
+            let progress = (t - self.attack - self.sustain) / self.decay;
+            (1.0 - progress).max(0.0)
+        }
+    }
+}
+
+/// Render `params` into mono PCM at `sample_rate`
+pub fn render(params: &SfxParams, sample_rate: f32, rng: &mut Rng) -> Vec<f32> {
+    let total = (params.duration() * sample_rate).ceil() as usize;
+    let mut out = Vec::with_capacity(total);
+
+    let mut phase = 0.0f32;
+    // Noise waveform holds a random value for 1/8 of a period so it stays pitched
+    let mut noise_hold = rng.signed() as f32;
+    let mut filtered = 0.0f32;
+
+    for i in 0..total {
+        let t = i as f32 / sample_rate;
+
+        let mut freq = params.base_freq * (params.freq_slide * t).exp2();
+        if freq < params.min_freq {
+            break;
+        }
+        if params.vibrato_depth > 0.0 {
+            freq *= 1.0 + params.vibrato_depth * (TAU * params.vibrato_speed * t).sin();
+        }
+
+        let previous_phase = phase;
+        phase = (phase + freq / sample_rate).fract();
+        if (phase * 8.0).floor() != (previous_phase * 8.0).floor() {
+            noise_hold = rng.signed() as f32;
+        }
+
+        let wave = match params.waveform {
+            Waveform::Square => if phase < params.duty { 1.0 } else { -1.0 },
+            Waveform::Sawtooth => 2.0 * phase - 1.0,
+            Waveform::Sine => (TAU * phase).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Noise => noise_hold,
+        };
+        let mut sample = if params.noise > 0.0 {
+            wave * (1.0 - params.noise) + rng.signed() as f32 * params.noise
+        } else {
+            wave
+        };
+
+        if let Some(cutoff) = params.lowpass {
+            let cutoff = (cutoff * (params.lowpass_sweep * t).exp2()).min(sample_rate * 0.45);
+            let alpha = 1.0 - (-TAU * cutoff / sample_rate).exp();
+            filtered += alpha * (sample - filtered);
+            sample = filtered;
+        }
+
+        out.push((sample * params.envelope(t) * params.volume).clamp(-1.0, 1.0));
+    }
+
+    out
+}
+
+/// Render one varied play of `sound`
+pub fn render_sound(sound: Sound, sample_rate: f32) -> Vec<f32> {
+    let params = SfxParams::for_play(sound);
+    VARIATION_RNG.with(|rng| render(&params, sample_rate, &mut rng.borrow_mut()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: f32 = 22_050.0;
+
+    #[test]
+    fn presets_render_audible_bounded_pcm() {
+        for sound in Sound::ALL {
+            let params = SfxParams::preset(sound);
+            let pcm = render(&params, RATE, &mut Rng::new(1));
+            assert!(!pcm.is_empty(), "{sound:?} rendered nothing");
+            assert!(pcm.len() <= (params.duration() * RATE).ceil() as usize);
+            assert!(pcm.iter().all(|s| (-1.0..=1.0).contains(s)), "{sound:?} clips");
+            assert!(pcm.iter().any(|&s| s != 0.0), "{sound:?} is silent");
+        }
+    }
+
+    #[test]
+    fn envelope_rises_peaks_and_fades() {
+        let params = SfxParams { attack: 0.1, sustain: 0.2, decay: 0.3, punch: 0.5, ..SfxParams::default() };
+        assert_eq!(params.envelope(0.0), 0.0);
+        assert!((params.envelope(0.05) - 0.5).abs() < 1e-6);
+        assert!((params.envelope(0.1) - 1.5).abs() < 1e-6, "punch at the start of sustain");
+        assert!(params.envelope(0.3) <= 1.0 + 1e-6);
+        assert_eq!(params.envelope(0.6), 0.0);
+        assert_eq!(params.envelope(1.0), 0.0);
+    }
+
+    #[test]
+    fn variation_stays_within_its_jitter() {
+        let base = SfxParams::preset(Sound::Shoot);
+        let mut rng = Rng::new(7);
+        let amount = 0.1;
+        for _ in 0..200 {
+            let varied = base.varied(amount, &mut rng);
+            let within = |value: f32, original: f32, scale: f32| (value / original - 1.0).abs() <= amount * scale + 1e-6;
+            assert!(within(varied.base_freq, base.base_freq, 1.0));
+            assert!(within(varied.sustain, base.sustain, 0.5));
+            assert!(within(varied.decay, base.decay, 1.0));
+            assert!(within(varied.lowpass.unwrap(), base.lowpass.unwrap(), 1.0));
+            assert_eq!(varied.waveform, base.waveform);
+        }
+    }
+}
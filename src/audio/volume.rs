@@ -0,0 +1,110 @@
+//! Player-facing volume settings, persisted across sessions and applied to
+//! the mixer bus gains
+
+use std::cell::Cell;
+use crate::audio::mixer::{with_mixer, Mixer};
+use crate::settings::{self as persist, Persisted, Persistent};
+
+/// Time constant for gain changes, short enough to feel instant without clicks
+const RAMP_SECS: f64 = 0.02;
+
+thread_local! {
+    static SETTINGS: Persistent<VolumeSettings> = const { Persistent::new() };
+    static TAB_HIDDEN: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VolumeSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub muted: bool,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        VolumeSettings { master: 0.8, music: 0.6, sfx: 0.5, muted: false }
+    }
+}
+
+/// Saved as `master=0.80;music=0.60;sfx=0.50;muted=0`
+impl Persisted for VolumeSettings {
+    const STORAGE_KEY: &'static str = "volume";
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "master" => self.master = persist::parse_number(value).unwrap_or(self.master),
+            "music" => self.music = persist::parse_number(value).unwrap_or(self.music),
+            "sfx" => self.sfx = persist::parse_number(value).unwrap_or(self.sfx),
+            "muted" => self.muted = persist::parse_flag(value).unwrap_or(self.muted),
+            _ => {}
+        }
+    }
+
+    fn pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("master", format!("{:.2}", self.master)),
+            ("music", format!("{:.2}", self.music)),
+            ("sfx", format!("{:.2}", self.sfx)),
+            ("muted", persist::flag(self.muted)),
+        ]
+    }
+
+    fn sanitize(&mut self) {
+        self.master = self.master.clamp(0.0, 1.0);
+        self.music = self.music.clamp(0.0, 1.0);
+        self.sfx = self.sfx.clamp(0.0, 1.0);
+    }
+}
+
+pub fn settings() -> VolumeSettings {
+    SETTINGS.with(Persistent::get)
+}
+
+/// Mutate the settings, persist them and push them into the audio graph
+pub fn update(f: impl FnOnce(&mut VolumeSettings)) {
+    SETTINGS.with(|s| s.update(f));
+    with_mixer(apply);
+}
+
+/// Silence everything while the tab is in the background, without touching
+/// the saved settings
+pub fn set_tab_hidden(hidden: bool) {
+    TAB_HIDDEN.with(|h| h.set(hidden));
+    with_mixer(apply);
+}
+
+/// Write the current settings into the mixer's gain nodes
+pub fn apply(mixer: &Mixer) {
+    let settings = settings();
+    let silent = settings.muted || TAB_HIDDEN.with(Cell::get);
+    let master = if silent { 0.0 } else { settings.master };
+    let now = mixer.ctx.current_time();
+
+    let _ = mixer.master.gain().set_target_at_time(master, now, RAMP_SECS);
+    let _ = mixer.music.gain().set_target_at_time(settings.music, now, RAMP_SECS);
+    let _ = mixer.sfx.gain().set_target_at_time(settings.sfx, now, RAMP_SECS);
+    // Menu feedback follows the effects level rather than having its own slider
+    let _ = mixer.ui.gain().set_target_at_time(settings.sfx, now, RAMP_SECS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let settings = VolumeSettings { master: 0.3, music: 1.0, sfx: 0.0, muted: true };
+        assert_eq!(VolumeSettings::parse(&settings.serialize()), settings);
+    }
+
+    #[test]
+    fn garbage_keeps_the_defaults() {
+        let defaults = VolumeSettings::default();
+        assert_eq!(VolumeSettings::parse("not even close"), defaults);
+        let parsed = VolumeSettings::parse("master=NaN;music=inf;sfx=loud;muted=maybe;volume=11");
+        assert_eq!(parsed, defaults, "non-finite and malformed values are rejected");
+        assert_eq!(VolumeSettings::parse("master=7;sfx=-1").master, 1.0);
+        assert_eq!(VolumeSettings::parse("master=7;sfx=-1").sfx, 0.0);
+    }
+}
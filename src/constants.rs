@@ -1,6 +1,8 @@
 // Game constants
-pub const CANVAS_WIDTH: f64 = 800.0;
-pub const CANVAS_HEIGHT: f64 = 600.0;
+pub const WORLD_WIDTH: f64 = 800.0;
+pub const WORLD_HEIGHT: f64 = 600.0;
 pub const PLANET_RADIUS: f64 = 50.0;
 pub const ORBIT_RADIUS: f64 = 150.0;
-pub const PLAYER_SIZE: f64 = 20.0;
\ No newline at end of file
+pub const PLAYER_SIZE: f64 = 20.0;
+/// Threats spawn on a circle this far from the planet
+pub const THREAT_SPAWN_DISTANCE: f64 = WORLD_WIDTH * 0.6;
\ No newline at end of file
@@ -0,0 +1,60 @@
+//! Canvas sizing and fullscreen
+//!
+//! The canvas is laid out in CSS pixels to fit the window (or the whole
+//! screen when fullscreen), and its backing store is that size times
+//! `devicePixelRatio`, so lines and text stay sharp on HiDPI screens. The
+//! renderer letterboxes the world into whatever backing size results.
+
+use wasm_bindgen::JsValue;
+use web_sys::{window, HtmlCanvasElement};
+use crate::constants::{WORLD_HEIGHT, WORLD_WIDTH};
+
+/// Room kept free around the canvas for the title and instructions, in CSS pixels
+const CHROME_WIDTH: f64 = 40.0;
+const CHROME_HEIGHT: f64 = 170.0;
+
+fn is_fullscreen() -> bool {
+    window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.fullscreen_element())
+        .is_some()
+}
+
+/// Size `canvas` for the current window and return its backing size in device pixels
+pub fn fit_canvas(canvas: &HtmlCanvasElement) -> Result<(f64, f64), JsValue> {
+    let window = window().ok_or("no window")?;
+    let inner_width = window.inner_width()?.as_f64().unwrap_or(WORLD_WIDTH);
+    let inner_height = window.inner_height()?.as_f64().unwrap_or(WORLD_HEIGHT);
+
+    // Fullscreen takes the whole screen and lets the renderer draw the bars;
+    // windowed, the canvas itself keeps the aspect so the page chrome hugs it
+    let (css_width, css_height) = if is_fullscreen() {
+        (inner_width, inner_height)
+    } else {
+        let fit = ((inner_width - CHROME_WIDTH) / WORLD_WIDTH)
+            .min((inner_height - CHROME_HEIGHT) / WORLD_HEIGHT)
+            .max(0.25);
+        ((WORLD_WIDTH * fit).floor(), (WORLD_HEIGHT * fit).floor())
+    };
+
+    let ratio = window.device_pixel_ratio().max(1.0);
+    let (width, height) = ((css_width * ratio).round(), (css_height * ratio).round());
+    canvas.set_attribute("style", &format!("width: {css_width}px; height: {css_height}px"))?;
+    if canvas.width() != width as u32 || canvas.height() != height as u32 {
+        canvas.set_width(width as u32);
+        canvas.set_height(height as u32);
+    }
+    Ok((width, height))
+}
+
+/// Enter fullscreen on `#game-container`, or leave it
+pub fn toggle_fullscreen() {
+    let Some(document) = window().and_then(|w| w.document()) else { return };
+    if document.fullscreen_element().is_some() {
+        document.exit_fullscreen();
+    } else if let Some(container) = document.get_element_by_id("game-container") {
+        if let Err(err) = container.request_fullscreen() {
+            log::warn!("Fullscreen refused: {:?}", err);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Achievements: one-off goals checked against the run's stats
+//!
+//! The simulation only decides what this game has earned (`GameState::
+//! unlocked`) and queues a toast for each new one, so it stays
+//! deterministic and never touches storage mid-tick. The game loop hands
+//! the set to `record` every frame, which merges it into the player's
+//! saved progress.
+
+use std::cell::Cell;
+use crate::game::ecs::Group;
+use crate::game::state::GameState;
+use crate::storage;
+
+const STORAGE_KEY: &str = "achievements";
+
+/// How long a toast stays up, including sliding in and out
+pub const TOAST_SECS: f64 = 4.0;
+
+thread_local! {
+    static PROGRESS: Cell<Unlocked> = Cell::new(Unlocked::load());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Achievement {
+    ChainReaction,
+    Survivor,
+    Flawless,
+    DeadEye,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::ChainReaction,
+        Achievement::Survivor,
+        Achievement::Flawless,
+        Achievement::DeadEye,
+    ];
+
+    /// Stable name for saves; never rename
+    fn id(self) -> &'static str {
+        match self {
+            Achievement::ChainReaction => "chain_reaction",
+            Achievement::Survivor => "survivor",
+            Achievement::Flawless => "flawless",
+            Achievement::DeadEye => "dead_eye",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Achievement::ChainReaction => "CHAIN REACTION",
+            Achievement::Survivor => "SURVIVOR",
+            Achievement::Flawless => "FLAWLESS",
+            Achievement::DeadEye => "DEAD EYE",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Achievement::ChainReaction => "Reach a 25x combo",
+            Achievement::Survivor => "Survive for 5 minutes",
+            Achievement::Flawless => "Clear a wave without the planet taking damage",
+            Achievement::DeadEye => "Clear a wave without missing a shot",
+        }
+    }
+}
+
+/// A set of achievements, small enough to copy around freely
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Unlocked(u32);
+
+impl Unlocked {
+    pub fn contains(self, achievement: Achievement) -> bool {
+        self.0 & (1 << achievement as u32) != 0
+    }
+
+    pub fn insert(&mut self, achievement: Achievement) {
+        self.0 |= 1 << achievement as u32;
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Members in `Achievement::ALL` order
+    pub fn iter(self) -> impl Iterator<Item = Achievement> {
+        Achievement::ALL.into_iter().filter(move |&a| self.contains(a))
+    }
+
+    fn union(self, other: Unlocked) -> Unlocked {
+        Unlocked(self.0 | other.0)
+    }
+
+    fn load() -> Self {
+        storage::load(STORAGE_KEY)
+            .map(|raw| Self::parse(&raw))
+            .unwrap_or_default()
+    }
+
+    /// Parse `survivor,dead_eye`, skipping names this build doesn't know
+    fn parse(raw: &str) -> Self {
+        let mut unlocked = Self::default();
+        for id in raw.split(',').map(str::trim) {
+            if let Some(achievement) = Achievement::ALL.into_iter().find(|a| a.id() == id) {
+                unlocked.insert(achievement);
+            }
+        }
+        unlocked
+    }
+
+    fn serialize(self) -> String {
+        self.iter().map(Achievement::id).collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Everything the player has unlocked, in any game
+pub fn progress() -> Unlocked {
+    PROGRESS.with(Cell::get)
+}
+
+/// Merge a game's unlocks into the saved progress; only writes when
+/// something is new
+pub fn record(unlocked: Unlocked) {
+    let saved = progress();
+    let merged = saved.union(unlocked);
+    if merged != saved {
+        PROGRESS.with(|p| p.set(merged));
+        storage::save(STORAGE_KEY, &merged.serialize());
+    }
+}
+
+/// Unlocks waiting for their toast, first come first served. Each
+/// achievement unlocks once a game, so there is always room.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct ToastQueue {
+    queued: [Option<Achievement>; Achievement::ALL.len()],
+    len: usize,
+}
+
+impl ToastQueue {
+    pub fn push(&mut self, achievement: Achievement) {
+        if let Some(slot) = self.queued.get_mut(self.len) {
+            *slot = Some(achievement);
+            self.len += 1;
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Achievement> {
+        let next = self.queued[0].take()?;
+        self.queued.rotate_left(1);
+        self.len -= 1;
+        Some(next)
+    }
+}
+
+/// An unlock being announced
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Toast {
+    pub achievement: Achievement,
+    /// Seconds left on screen, counting down from `TOAST_SECS`
+    pub remaining: f64,
+}
+
+impl GameState {
+    /// Award `achievement` if this game doesn't have it yet, queueing a toast
+    pub fn unlock(&mut self, achievement: Achievement) {
+        if self.unlocked.contains(achievement) {
+            return;
+        }
+        self.unlocked.insert(achievement);
+        self.pending_toasts.push(achievement);
+        log::info!("Achievement unlocked: {}", achievement.title());
+    }
+
+    /// Goals that can be met at any moment, and the toast queue
+    pub(crate) fn check_achievements(&mut self, dt: f64) {
+        if self.combo >= 25 {
+            self.unlock(Achievement::ChainReaction);
+        }
+        if self.time >= 300.0 {
+            self.unlock(Achievement::Survivor);
+        }
+
+        if let Some(toast) = &mut self.toast {
+            toast.remaining -= dt;
+            if toast.remaining <= 0.0 {
+                self.toast = None;
+            }
+        }
+        if self.toast.is_none() {
+            if let Some(next) = self.pending_toasts.pop() {
+                self.toast = Some(Toast { achievement: next, remaining: TOAST_SECS });
+            }
+        }
+    }
+
+    /// Goals judged over a whole wave; called as it's cleared
+    pub(crate) fn check_wave_achievements(&mut self) {
+        let (now, start) = (self.stats, self.wave_start_stats);
+        if now.damage_taken == start.damage_taken {
+            self.unlock(Achievement::Flawless);
+        }
+        // A shot still in flight hasn't hit yet, so it can't count as one;
+        // shots a full pool recycled are already misses
+        let in_flight = self.world.len(Group::Projectiles);
+        if now.hits > start.hits && now.misses == start.misses && in_flight == 0 {
+            self.unlock(Achievement::DeadEye);
+        }
+        self.wave_start_stats = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Capacities;
+
+    #[test]
+    fn saved_progress_round_trips() {
+        let mut unlocked = Unlocked::default();
+        unlocked.insert(Achievement::DeadEye);
+        unlocked.insert(Achievement::Survivor);
+        assert_eq!(unlocked.serialize(), "survivor,dead_eye");
+        assert_eq!(Unlocked::parse("survivor, dead_eye,from_the_future"), unlocked);
+        assert_eq!(unlocked.len(), 2);
+    }
+
+    #[test]
+    fn unlocks_toast_once_each_in_turn() {
+        let mut state = GameState::with_seed(1);
+        // Deliberately out of declaration order
+        state.unlock(Achievement::DeadEye);
+        state.unlock(Achievement::Survivor);
+        state.unlock(Achievement::ChainReaction);
+        state.unlock(Achievement::Survivor);
+
+        state.check_achievements(0.0);
+        for expected in [Achievement::DeadEye, Achievement::Survivor, Achievement::ChainReaction] {
+            assert_eq!(state.toast.map(|t| t.achievement), Some(expected), "toasts come in unlock order");
+            state.check_achievements(TOAST_SECS);
+        }
+        assert_eq!(state.toast, None);
+        assert_eq!(state.unlocked.len(), 3);
+    }
+
+    #[test]
+    fn waves_are_judged_on_their_own() {
+        let mut state = GameState::with_seed(1);
+        state.stats.hits = 8;
+        state.stats.damage_taken = 10;
+        state.check_wave_achievements();
+        assert!(state.unlocked.contains(Achievement::DeadEye));
+        assert!(!state.unlocked.contains(Achievement::Flawless));
+
+        // Next wave: no damage, but a shot went wide
+        state.stats.hits += 5;
+        state.stats.misses += 1;
+        state.check_wave_achievements();
+        assert!(state.unlocked.contains(Achievement::Flawless));
+    }
+
+    #[test]
+    fn dead_eye_waits_for_every_shot() {
+        let mut state = GameState::with_seed(1);
+        state.stats.hits = 8;
+        state.shoot();
+        state.check_wave_achievements();
+        assert!(!state.unlocked.contains(Achievement::DeadEye), "a shot is still in flight");
+
+        // The first shot is recycled by a full pool before it can land; the
+        // second one hits
+        let mut state = GameState::with_capacities(1, Capacities { projectiles: 1, ..Capacities::default() });
+        state.shoot();
+        state.shoot();
+        let landed: Vec<_> = state.world.iter().collect();
+        landed.into_iter().for_each(|shot| state.world.despawn(shot));
+        state.stats.record_hit();
+        state.check_wave_achievements();
+        assert!(!state.unlocked.contains(Achievement::DeadEye), "the recycled shot missed");
+    }
+}
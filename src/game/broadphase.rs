@@ -0,0 +1,156 @@
+//! Uniform grid broadphase for circle collisions
+//!
+//! Every tick the grid is rebuilt from scratch: each circle is filed under
+//! the cell holding its centre (a counting sort into one flat array, so a
+//! rebuild allocates nothing once the buffers have grown). A query visits
+//! the cells within its radius plus the largest radius filed, so big
+//! circles are still found from neighbouring cells without
+//! being stored more than once. Positions past the edge clamp to the
+//! border cells, which keeps far-off entities correct, just unaccelerated.
+
+use crate::constants::*;
+
+/// Cell edge in world units; around two threat diameters
+const CELL_SIZE: f64 = 64.0;
+/// How far past the world edge the grid reaches; threats spawn off screen
+const MARGIN: f64 = 256.0;
+
+#[derive(Debug)]
+pub struct SpatialGrid {
+    min_x: f64,
+    min_y: f64,
+    cols: usize,
+    rows: usize,
+    /// Entries for cell `c` are `entries[starts[c]..starts[c + 1]]`
+    starts: Vec<u32>,
+    entries: Vec<u32>,
+    /// (cell, id) pairs gathered by `rebuild` before sorting
+    staged: Vec<(u32, u32)>,
+    max_radius: f64,
+}
+
+impl SpatialGrid {
+    /// Grid over the world and `MARGIN` around it, with room for `capacity`
+    /// circles before a rebuild has to allocate
+    pub fn with_capacity(capacity: usize) -> Self {
+        let cols = ((WORLD_WIDTH + 2.0 * MARGIN) / CELL_SIZE).ceil() as usize;
+        let rows = ((WORLD_HEIGHT + 2.0 * MARGIN) / CELL_SIZE).ceil() as usize;
+        SpatialGrid {
+            min_x: -MARGIN,
+            min_y: -MARGIN,
+            cols,
+            rows,
+            starts: vec![0; cols * rows + 1],
+            entries: Vec::with_capacity(capacity),
+            staged: Vec::with_capacity(capacity),
+            max_radius: 0.0,
+        }
+    }
+
+    fn col(&self, x: f64) -> usize {
+        (((x - self.min_x) / CELL_SIZE).floor().max(0.0) as usize).min(self.cols - 1)
+    }
+
+    fn row(&self, y: f64) -> usize {
+        (((y - self.min_y) / CELL_SIZE).floor().max(0.0) as usize).min(self.rows - 1)
+    }
+
+    /// Replace the contents with `(id, x, y, radius)` circles
+    pub fn rebuild(&mut self, circles: impl Iterator<Item = (u32, f64, f64, f64)>) {
+        self.staged.clear();
+        self.max_radius = 0.0;
+        for (id, x, y, radius) in circles {
+            let cell = self.row(y) * self.cols + self.col(x);
+            self.staged.push((cell as u32, id));
+            self.max_radius = self.max_radius.max(radius);
+        }
+
+        // Counting sort: tally each cell at starts[cell + 1], add up so
+        // starts[cell] is where the cell begins, then place. Placing walks
+        // each start forward to the cell's end, i.e. the next cell's start,
+        // so one shift puts the table back.
+        self.starts.fill(0);
+        for &(cell, _) in &self.staged {
+            self.starts[cell as usize + 1] += 1;
+        }
+        for c in 1..self.starts.len() {
+            self.starts[c] += self.starts[c - 1];
+        }
+        self.entries.clear();
+        self.entries.resize(self.staged.len(), 0);
+        for &(cell, id) in &self.staged {
+            let cursor = &mut self.starts[cell as usize];
+            self.entries[*cursor as usize] = id;
+            *cursor += 1;
+        }
+        self.starts.rotate_right(1);
+        self.starts[0] = 0;
+    }
+
+    /// Ids of every circle that could touch a circle of `radius` at (`x`, `y`)
+    pub fn query(&self, x: f64, y: f64, radius: f64) -> impl Iterator<Item = u32> + '_ {
+        let reach = radius + self.max_radius;
+        let (c0, c1) = (self.col(x - reach), self.col(x + reach));
+        let (r0, r1) = (self.row(y - reach), self.row(y + reach));
+        (r0..=r1).flat_map(move |row| {
+            let cells = row * self.cols + c0..=row * self.cols + c1;
+            let (start, end) = (self.starts[*cells.start()], self.starts[cells.end() + 1]);
+            self.entries[start as usize..end as usize].iter().copied()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::physics::check_collision;
+    use crate::rng::Rng;
+
+    fn circles(rng: &mut Rng, count: u32, radius: (f64, f64)) -> Vec<(u32, f64, f64, f64)> {
+        (0..count)
+            .map(|id| {
+                let x = rng.range(-400.0, WORLD_WIDTH + 400.0);
+                let y = rng.range(-400.0, WORLD_HEIGHT + 400.0);
+                (id, x, y, rng.range(radius.0, radius.1))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_exactly_the_brute_force_pairs() {
+        let mut rng = Rng::new(42);
+        let targets = circles(&mut rng, 600, (8.0, 45.0));
+        let probes = circles(&mut rng, 600, (3.0, 6.0));
+
+        let mut grid = SpatialGrid::with_capacity(0);
+        grid.rebuild(targets.iter().copied());
+
+        for &(_, px, py, pr) in &probes {
+            let mut found: Vec<u32> = grid
+                .query(px, py, pr)
+                .filter(|&id| {
+                    let (_, tx, ty, tr) = targets[id as usize];
+                    check_collision(px, py, pr, tx, ty, tr)
+                })
+                .collect();
+            found.sort();
+            let expected: Vec<u32> = targets
+                .iter()
+                .filter(|&&(_, tx, ty, tr)| check_collision(px, py, pr, tx, ty, tr))
+                .map(|&(id, ..)| id)
+                .collect();
+            assert_eq!(found, expected, "probe at ({px:.0}, {py:.0})");
+        }
+    }
+
+    #[test]
+    fn every_entry_is_listed_once() {
+        let mut rng = Rng::new(7);
+        let targets = circles(&mut rng, 300, (1.0, 10.0));
+        let mut grid = SpatialGrid::with_capacity(0);
+        grid.rebuild(targets.iter().copied());
+        let mut all: Vec<u32> = grid.query(WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0, 10_000.0).collect();
+        all.sort();
+        assert_eq!(all, (0..300).collect::<Vec<_>>());
+    }
+}
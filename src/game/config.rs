@@ -0,0 +1,340 @@
+//! Balance numbers, from `config.toml` (embedded at compile time) plus
+//! optional overrides
+//!
+//! Overrides are partial TOML documents layered over the defaults, key by
+//! key, before anything is deserialized, so an override only has to name
+//! what it changes. A bad override is reported and leaves the defaults
+//! standing; the embedded file itself must always load.
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use toml::{Table, Value};
+use crate::constants::{THREAT_SPAWN_DISTANCE, WORLD_HEIGHT, WORLD_WIDTH};
+use crate::game::difficulty::{Difficulty, Scaling};
+use crate::settings::Choice;
+
+const DEFAULTS: &str = include_str!("config.toml");
+
+static DEFAULT: Lazy<GameConfig> = Lazy::new(|| {
+    GameConfig::parse(&[DEFAULTS]).expect("game/config.toml is invalid")
+});
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameConfig {
+    pub player: PlayerConfig,
+    pub projectile: ProjectileConfig,
+    pub planet: PlanetConfig,
+    pub threats: ThreatConfig,
+    pub waves: WaveConfig,
+    pub combo: ComboConfig,
+    pub shake: ShakeConfig,
+    pub difficulty: DifficultyConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerConfig {
+    pub turn_speed: f64,
+    pub idle_speed: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectileConfig {
+    pub speed: f64,
+    pub radius: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlanetConfig {
+    pub health: i32,
+    pub impact_damage: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThreatConfig {
+    pub speed: [f64; 2],
+    pub radius: [f64; 2],
+    pub first_spawn_secs: f64,
+    pub cull_margin: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveConfig {
+    pub base_size: u32,
+    pub growth: u32,
+    pub spawn_interval: [f64; 2],
+    pub ramp_secs: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComboConfig {
+    pub points: u32,
+    pub window_secs: f64,
+    pub max_multiplier: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShakeConfig {
+    pub max: f64,
+    pub planet_hit: f64,
+    pub decay_per_sec: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DifficultyConfig {
+    pub easy: Scaling,
+    pub normal: Scaling,
+    pub hard: Scaling,
+    pub nightmare: Scaling,
+    pub adaptive: AdaptiveConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveConfig {
+    pub memory_secs: f64,
+    pub accuracy_target: f64,
+    pub damage_target: f64,
+    pub response: f64,
+    pub range: [f64; 2],
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        DEFAULT.clone()
+    }
+}
+
+impl GameConfig {
+    /// The defaults with `overrides` (TOML documents, later ones winning)
+    /// laid over them
+    pub fn with_overrides(overrides: &[&str]) -> Result<Self, String> {
+        let mut layers = vec![DEFAULTS];
+        layers.extend_from_slice(overrides);
+        Self::parse(&layers)
+    }
+
+    /// The defaults, then `file`, then the dotted keys of the query string.
+    /// Each layer is checked on top of the ones accepted before it, and one
+    /// that doesn't load is dropped on its own with the reason logged, so a
+    /// bad tweak never stops the game or takes a good layer down with it.
+    pub fn load(file: Option<&str>, search: &str) -> Self {
+        let query = query_overrides(search);
+        let mut accepted = Vec::new();
+        let mut config = Self::default();
+        for layer in file.into_iter().chain([query.as_str()]) {
+            accepted.push(layer);
+            match Self::with_overrides(&accepted) {
+                Ok(layered) => config = layered,
+                Err(err) => {
+                    log::warn!("Config override ignored: {}", err);
+                    accepted.pop();
+                }
+            }
+        }
+        config
+    }
+
+    fn parse(layers: &[&str]) -> Result<Self, String> {
+        let mut merged = Table::new();
+        for layer in layers {
+            let table: Table = layer.parse().map_err(|err| format!("{err}"))?;
+            merge(&mut merged, table);
+        }
+        let config: GameConfig = Value::Table(merged).try_into().map_err(|err| format!("{err}"))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Catch numbers that would break the game rather than just unbalance it
+    pub fn validate(&self) -> Result<(), String> {
+        let ranges = [
+            ("threats.speed", self.threats.speed),
+            ("threats.radius", self.threats.radius),
+        ];
+        for (name, [min, max]) in ranges {
+            if !(min > 0.0 && min <= max) {
+                return Err(format!("{name} must be [min, max] with 0 < min <= max"));
+            }
+        }
+        let ProjectileConfig { speed, radius } = self.projectile;
+        if !(speed > 0.0 && radius > 0.0) {
+            return Err("projectile.speed and radius must be positive".into());
+        }
+        let [start, floor] = self.waves.spawn_interval;
+        if !(floor > 0.0 && floor <= start) {
+            return Err("waves.spawn_interval must be [start, floor] with 0 < floor <= start".into());
+        }
+        let ThreatConfig { first_spawn_secs, cull_margin, .. } = self.threats;
+        if !(first_spawn_secs >= 0.0 && first_spawn_secs.is_finite()) {
+            return Err("threats.first_spawn_secs must be a finite number of seconds, zero or more".into());
+        }
+        // The spawn circle pokes furthest past the top and bottom edges
+        let overhang = THREAT_SPAWN_DISTANCE - WORLD_WIDTH.min(WORLD_HEIGHT) / 2.0;
+        if !(cull_margin >= overhang && cull_margin.is_finite()) {
+            return Err(format!("threats.cull_margin must be at least {overhang}, or threats are culled as they spawn"));
+        }
+        let shake = &self.shake;
+        if !(shake.max > 0.0 && shake.planet_hit >= 0.0 && shake.decay_per_sec > 0.0) {
+            return Err("shake.max and decay_per_sec must be positive, planet_hit can't be negative".into());
+        }
+        let ramp_secs = self.waves.ramp_secs;
+        if !(ramp_secs > 0.0 && ramp_secs.is_finite()) {
+            return Err("waves.ramp_secs must be a positive number of seconds".into());
+        }
+        if self.waves.base_size == 0 {
+            return Err("waves.base_size must be at least 1".into());
+        }
+        if self.combo.max_multiplier == 0 {
+            return Err("combo.max_multiplier must be at least 1".into());
+        }
+        for &level in Difficulty::ALL {
+            let Scaling { spawn_rate, threat_speed, threat_hp, planet_damage } = self.difficulty.preset(level);
+            if !(spawn_rate > 0.0 && threat_speed > 0.0 && threat_hp > 0.0 && planet_damage >= 0.0) {
+                let name = level.label().to_ascii_lowercase();
+                return Err(format!("difficulty.{name} multipliers must be positive"));
+            }
+        }
+        let adaptive = &self.difficulty.adaptive;
+        let [min, max] = adaptive.range;
+        if !(min > 0.0 && min <= 1.0 && 1.0 <= max) {
+            return Err("difficulty.adaptive.range must be [min, max] with 0 < min <= 1 <= max".into());
+        }
+        if !(adaptive.memory_secs > 0.0 && adaptive.damage_target > 0.0) {
+            return Err("difficulty.adaptive.memory_secs and damage_target must be positive".into());
+        }
+        Ok(())
+    }
+
+    /// Fastest a threat ever moves on any difficulty, world units per tick
+    pub fn max_threat_speed(&self) -> f64 {
+        let fastest = Difficulty::ALL.iter().map(|&level| self.difficulty.preset(level).threat_speed).fold(0.0, f64::max);
+        self.threats.speed[1] * fastest * self.difficulty.adaptive.range[1]
+    }
+
+    /// Kills it takes to clear `wave`
+    pub fn threats_in_wave(&self, wave: u32) -> u32 {
+        self.waves.base_size + self.waves.growth * wave.saturating_sub(1)
+    }
+}
+
+/// Overwrite `base` with `layer`, descending into tables so only the keys
+/// `layer` names are replaced
+fn merge(base: &mut Table, layer: Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(existing)), Value::Table(table)) => merge(existing, table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Turn the dotted keys of a query string (`?projectile.speed=12&...`)
+/// into a TOML override. Keys without a dot belong to something else and
+/// are skipped; values that aren't valid TOML are taken as strings.
+pub fn query_overrides(search: &str) -> String {
+    let mut toml = String::new();
+    for pair in search.trim_start_matches('?').split('&') {
+        let Some((key, value)) = pair.split_once('=') else { continue };
+        let dotted = key.split('.').count() > 1
+            && key.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        if !dotted {
+            continue;
+        }
+        let value = percent_decode(value);
+        let literal = if format!("v = {value}").parse::<Table>().is_ok() { value } else { format!("{value:?}") };
+        toml.push_str(&format!("{key} = {literal}\n"));
+    }
+    toml
+}
+
+/// Value of `key` in a query string, decoded
+pub fn query_param(search: &str, key: &str) -> Option<String> {
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.split_once('=').filter(|(k, _)| *k == key))
+        .map(|(_, value)| percent_decode(value))
+}
+
+/// `%XX` escapes and `+` for space, as browsers encode query values
+fn percent_decode(raw: &str) -> String {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut rest = raw.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => match rest.get(..2).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()) {
+                Some(decoded) => {
+                    bytes.push(decoded);
+                    rest = &rest[2..];
+                }
+                None => bytes.push(b'%'),
+            },
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_defaults_load() {
+        let config = GameConfig::default();
+        assert_eq!(config.projectile.speed, 8.0);
+        assert_eq!(config.threats_in_wave(1), 8);
+        assert_eq!(config.threats_in_wave(3), 16);
+    }
+
+    #[test]
+    fn overrides_replace_only_the_keys_they_name() {
+        let file = "[projectile]\nspeed = 12.0\n\n[combo]\nmax_multiplier = 4\n";
+        let query = query_overrides("?renderer=webgl2&projectile.speed=14&threats.speed=[1.0,%203.0]");
+        let config = GameConfig::with_overrides(&[file, &query]).unwrap();
+        assert_eq!(config.projectile.speed, 14.0, "the query wins over the file");
+        assert_eq!(config.projectile.radius, 5.0);
+        assert_eq!(config.combo.max_multiplier, 4);
+        assert_eq!(config.threats.speed, [1.0, 3.0]);
+        assert_eq!(config.combo.window_secs, GameConfig::default().combo.window_secs);
+    }
+
+    #[test]
+    fn bad_overrides_are_errors() {
+        assert!(GameConfig::with_overrides(&["[projectile]\nspeeed = 12.0"]).is_err(), "typo");
+        assert!(GameConfig::with_overrides(&["[projectile]\nspeed = \"fast\""]).is_err(), "wrong type");
+        assert!(GameConfig::with_overrides(&["[threats]\nspeed = [3.0, 1.0]"]).is_err(), "backwards range");
+        assert!(GameConfig::with_overrides(&["[projectile"]).is_err(), "not TOML");
+        assert!(GameConfig::with_overrides(&["[threats]\ncull_margin = 100.0"]).is_err(), "culls fresh spawns");
+        assert!(GameConfig::with_overrides(&["[shake]\ndecay_per_sec = 0.0"]).is_err(), "shake never settles");
+    }
+
+    #[test]
+    fn a_bad_file_falls_back_to_the_query() {
+        let config = GameConfig::load(Some("[projectile\n"), "?config=tuning.toml&projectile.radius=7");
+        assert_eq!(config.projectile.radius, 7.0);
+        assert_eq!(query_param("?config=my%20tuning.toml", "config").as_deref(), Some("my tuning.toml"));
+    }
+
+    #[test]
+    fn a_bad_query_keeps_the_good_file() {
+        let file = "[projectile]\nradius = 7.0\n";
+        let config = GameConfig::load(Some(file), "?projectile.speed=12&combo.max_multiplier=0");
+        assert_eq!(config.projectile.radius, 7.0);
+        assert_eq!(config.projectile.speed, GameConfig::default().projectile.speed, "the whole query goes");
+        assert_eq!(config.combo.max_multiplier, GameConfig::default().combo.max_multiplier);
+    }
+}
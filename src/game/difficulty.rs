@@ -0,0 +1,258 @@
+//! Difficulty presets and adaptive pacing
+//!
+//! A preset scales the balance numbers in `GameConfig`: how often threats
+//! spawn, how fast they fly, how much health they have and how hard they
+//! hit the planet. The multipliers themselves live in `config.toml` with
+//! the rest of the balance.
+//!
+//! Adaptive pacing is an optional layer on top. It keeps a running read on
+//! the player, their accuracy and the damage the planet has taken, with
+//! older ticks fading out over `memory_secs`. Doing better than the targets
+//! slowly raises a pace multiplier on spawn rate and threat speed, and
+//! struggling lowers it, always within `range`. It never touches health
+//! or damage, so a run still ends when the planet falls.
+
+use serde::Deserialize;
+use crate::game::config::{AdaptiveConfig, DifficultyConfig};
+use crate::game::state::GameState;
+use crate::settings::{self as persist, Choice, Persisted, Persistent};
+
+/// Below this many recent shots, accuracy is too noisy to act on
+const MIN_SHOTS: f64 = 3.0;
+
+thread_local! {
+    static SETTINGS: Persistent<DifficultySettings> = const { Persistent::new() };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl Choice for Difficulty {
+    const ALL: &'static [Self] = &[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Nightmare];
+
+    fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+            Difficulty::Nightmare => "NIGHTMARE",
+        }
+    }
+}
+
+/// Multipliers on the base balance; 1 leaves a number as configured
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scaling {
+    /// Divides the time between spawns
+    pub spawn_rate: f64,
+    pub threat_speed: f64,
+    /// Rounded, never below one hit
+    pub threat_hp: f64,
+    pub planet_damage: f64,
+}
+
+impl Scaling {
+    pub fn threat_hp(&self, base: u32) -> u32 {
+        ((base as f64 * self.threat_hp).round() as u32).max(1)
+    }
+
+    pub fn planet_damage(&self, base: i32) -> i32 {
+        (base as f64 * self.planet_damage).round() as i32
+    }
+}
+
+/// The player's choice, persisted across sessions
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct DifficultySettings {
+    pub level: Difficulty,
+    pub adaptive: bool,
+}
+
+/// Saved as `level=hard;adaptive=1`
+impl Persisted for DifficultySettings {
+    const STORAGE_KEY: &'static str = "difficulty";
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "level" => self.level = Difficulty::parse(value).unwrap_or(self.level),
+            "adaptive" => self.adaptive = persist::parse_flag(value).unwrap_or(self.adaptive),
+            _ => {}
+        }
+    }
+
+    fn pairs(&self) -> Vec<(&'static str, String)> {
+        vec![("level", self.level.save_value()), ("adaptive", persist::flag(self.adaptive))]
+    }
+}
+
+pub fn settings() -> DifficultySettings {
+    SETTINGS.with(Persistent::get)
+}
+
+/// Mutate the settings and persist them
+pub fn update(f: impl FnOnce(&mut DifficultySettings)) {
+    SETTINGS.with(|s| s.update(f));
+}
+
+/// Recent performance and the pace it has earned. Counts decay rather
+/// than sit in a window, so there's nothing to allocate or trim.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adaptive {
+    pub enabled: bool,
+    /// Multiplier on spawn rate and threat speed; 1 is the preset as written
+    pub pace: f64,
+    shots: f64,
+    hits: f64,
+    damage: f64,
+}
+
+impl Adaptive {
+    pub fn new(enabled: bool) -> Self {
+        Adaptive { enabled, pace: 1.0, shots: 0.0, hits: 0.0, damage: 0.0 }
+    }
+
+    pub fn record_shot(&mut self) {
+        self.shots += 1.0;
+    }
+
+    pub fn record_hit(&mut self) {
+        self.hits += 1.0;
+    }
+
+    pub fn record_damage(&mut self, amount: i32) {
+        self.damage += amount as f64;
+    }
+
+    /// Share of recent shots that hit something; the target until there
+    /// are enough shots to say
+    pub fn accuracy(&self, config: &AdaptiveConfig) -> f64 {
+        if self.shots < MIN_SHOTS { config.accuracy_target } else { (self.hits / self.shots).min(1.0) }
+    }
+
+    /// Recent planet damage as a rate per minute
+    pub fn damage_per_minute(&self, config: &AdaptiveConfig) -> f64 {
+        self.damage / config.memory_secs * 60.0
+    }
+
+    /// Fade old results and, if enabled, move the pace toward what they earn
+    pub fn update(&mut self, config: &AdaptiveConfig, dt: f64) {
+        let keep = (-dt / config.memory_secs).exp();
+        self.shots *= keep;
+        self.hits *= keep;
+        self.damage *= keep;
+        if !self.enabled {
+            return;
+        }
+        // Both in -1..1; positive means the player has room to spare
+        let skill = ((self.accuracy(config) - config.accuracy_target) * 2.0).clamp(-1.0, 1.0);
+        let strain = (self.damage_per_minute(config) / config.damage_target - 1.0).clamp(-1.0, 1.0);
+        let direction = (skill - strain).clamp(-1.0, 1.0);
+        let [min, max] = config.range;
+        self.pace = (self.pace + config.response * direction * dt).clamp(min, max);
+    }
+}
+
+impl DifficultyConfig {
+    pub fn preset(&self, level: Difficulty) -> Scaling {
+        match level {
+            Difficulty::Easy => self.easy,
+            Difficulty::Normal => self.normal,
+            Difficulty::Hard => self.hard,
+            Difficulty::Nightmare => self.nightmare,
+        }
+    }
+}
+
+impl GameState {
+    /// Switch preset or adaptive pacing mid-game; threats already in
+    /// flight keep what they spawned with
+    pub fn set_difficulty(&mut self, settings: DifficultySettings) {
+        self.difficulty = settings.level;
+        if self.adaptive.enabled != settings.adaptive {
+            self.adaptive = Adaptive::new(settings.adaptive);
+        }
+    }
+
+    /// The preset, with the adaptive pace folded in
+    pub fn scaling(&self) -> Scaling {
+        let preset = self.config.difficulty.preset(self.difficulty);
+        let pace = if self.adaptive.enabled { self.adaptive.pace } else { 1.0 };
+        Scaling { spawn_rate: preset.spawn_rate * pace, threat_speed: preset.threat_speed * pace, ..preset }
+    }
+
+    pub(crate) fn adapt_difficulty(&mut self, dt: f64) {
+        self.adaptive.update(&self.config.difficulty.adaptive, dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameConfig;
+
+    fn adaptive_config() -> AdaptiveConfig {
+        GameConfig::default().difficulty.adaptive
+    }
+
+    #[test]
+    fn presets_get_harder_in_order() {
+        let config = GameConfig::default().difficulty;
+        for pair in Difficulty::ALL.windows(2) {
+            let (easier, harder) = (config.preset(pair[0]), config.preset(pair[1]));
+            assert!(harder.spawn_rate >= easier.spawn_rate, "{:?}", pair);
+            assert!(harder.threat_speed >= easier.threat_speed, "{:?}", pair);
+            assert!(harder.threat_hp >= easier.threat_hp, "{:?}", pair);
+            assert!(harder.planet_damage >= easier.planet_damage, "{:?}", pair);
+        }
+        assert_eq!(config.normal, Scaling { spawn_rate: 1.0, threat_speed: 1.0, threat_hp: 1.0, planet_damage: 1.0 });
+        assert_eq!(config.easy.threat_hp(1), 1, "never below one hit");
+    }
+
+    #[test]
+    fn sharp_play_tightens_the_pace_within_bounds() {
+        let config = adaptive_config();
+        let mut adaptive = Adaptive::new(true);
+        for _ in 0..60 * 120 {
+            adaptive.record_shot();
+            adaptive.record_hit();
+            adaptive.update(&config, 1.0 / 60.0);
+        }
+        assert_eq!(adaptive.pace, config.range[1]);
+    }
+
+    #[test]
+    fn planet_damage_eases_the_pace() {
+        let config = adaptive_config();
+        let mut adaptive = Adaptive::new(true);
+        for tick in 0..60 * 120 {
+            if tick % 60 == 0 {
+                adaptive.record_damage(10);
+            }
+            adaptive.update(&config, 1.0 / 60.0);
+        }
+        assert!(adaptive.pace < 1.0);
+        assert!(adaptive.pace >= config.range[0]);
+
+        // Off, it still listens but never moves
+        let mut off = Adaptive::new(false);
+        off.record_damage(100);
+        off.update(&config, 1.0);
+        assert_eq!(off.pace, 1.0);
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let settings = DifficultySettings { level: Difficulty::Nightmare, adaptive: true };
+        assert_eq!(DifficultySettings::parse(&settings.serialize()), settings);
+        assert_eq!(DifficultySettings::parse("level=impossible;adaptive=1").level, Difficulty::Normal);
+        assert_eq!(Difficulty::Nightmare.step(1), Difficulty::Nightmare);
+        assert_eq!(Difficulty::Nightmare.cycle(), Difficulty::Easy);
+    }
+}
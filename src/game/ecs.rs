@@ -0,0 +1,345 @@
+//! Entities and components
+//!
+//! An entity is just a slot number; what it is comes from the components
+//! attached to it. A threat is a transform, a velocity, a collider, some
+//! health, the enemy team and a look; a particle is a transform, a
+//! velocity, a lifetime and a look. Systems (in `systems.rs` and
+//! `particles.rs`) run over every entity carrying the components they
+//! care about, so a new kind of thing is a new combination rather than a
+//! new vector and a new set of loops.
+//!
+//! Slots are split into groups, each with its own fixed budget and
+//! overflow policy (see `Pool`), so a storm of particles can never take
+//! room from threats. Groups are laid out one after another, which also
+//! fixes the order entities are visited and drawn in. Components live in
+//! one column per type, indexed by slot; nothing allocates after `new`.
+
+use crate::game::particles::Effect;
+use crate::game::pool::{Handle, Overflow, Pool};
+use crate::game::state::Capacities;
+
+/// Allocation budget an entity is spawned from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group {
+    /// Cosmetic; the oldest makes way when full
+    Particles,
+    /// Anything that can hurt the planet; spawning waits when full
+    Threats,
+    /// The player's shots; the oldest makes way when full
+    Projectiles,
+}
+
+impl Group {
+    pub const ALL: [Group; 3] = [Group::Particles, Group::Threats, Group::Projectiles];
+
+    fn overflow(self) -> Overflow {
+        match self {
+            Group::Threats => Overflow::Reject,
+            Group::Particles | Group::Projectiles => Overflow::RecycleOldest,
+        }
+    }
+
+    fn capacity(self, capacities: Capacities) -> usize {
+        match self {
+            Group::Particles => capacities.particles,
+            Group::Threats => capacities.threats,
+            Group::Projectiles => capacities.projectiles,
+        }
+    }
+}
+
+/// A live entity: its slot, plus the generation that tells it apart from
+/// whatever occupies the slot after it dies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entity {
+    index: u32,
+    group: Group,
+    handle: Handle,
+}
+
+impl Entity {
+    /// Slot number, unique among live entities; used as the broadphase id
+    pub fn index(self) -> u32 {
+        self.index
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// World units per tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Velocity {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collider {
+    pub radius: f64,
+}
+
+/// Hits left; the entity dies on the one that takes it to zero
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    pub hp: u32,
+}
+
+/// Seconds left, out of `total`; the entity dies when it runs out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lifetime {
+    pub remaining: f64,
+    pub total: f64,
+}
+
+impl Lifetime {
+    pub fn new(seconds: f64) -> Self {
+        Lifetime { remaining: seconds, total: seconds }
+    }
+
+    /// 0 at birth, 1 at death
+    pub fn age(&self) -> f64 {
+        1.0 - self.remaining / self.total
+    }
+}
+
+/// How the renderer draws an entity
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Renderable {
+    Threat,
+    Projectile,
+    /// Styled over its lifetime by the effect's preset; `size` scales the
+    /// preset's size curve so bursts aren't uniform
+    Particle { effect: Effect, size: f64 },
+}
+
+/// Which side an entity is on; player colliders hit enemy colliders
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Team {
+    Player,
+    Enemy,
+}
+
+/// One column of a component type, indexed by slot
+#[derive(Debug)]
+pub struct Storage<T>(Vec<Option<T>>);
+
+impl<T> Storage<T> {
+    fn new(slots: usize) -> Self {
+        Storage((0..slots).map(|_| None).collect())
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.0[entity.index as usize].as_ref()
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.0[entity.index as usize].as_mut()
+    }
+
+    pub fn insert(&mut self, entity: Entity, value: T) {
+        self.0[entity.index as usize] = Some(value);
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.0[entity.index as usize].take()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0[entity.index as usize].is_some()
+    }
+}
+
+/// A type that can be attached to an entity
+pub trait Component: Sized {
+    fn storage(world: &mut World) -> &mut Storage<Self>;
+}
+
+/// Declares the component columns; a new component is one line here
+macro_rules! components {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        #[derive(Debug)]
+        pub struct World {
+            groups: [(u32, Pool); 3],
+            $(pub $field: Storage<$ty>,)*
+        }
+
+        impl World {
+            pub fn new(capacities: Capacities) -> Self {
+                let mut start = 0;
+                let groups = Group::ALL.map(|group| {
+                    let capacity = group.capacity(capacities);
+                    let entry = (start, Pool::new(capacity, group.overflow()));
+                    start += capacity as u32;
+                    entry
+                });
+                let slots = start as usize;
+                World { groups, $($field: Storage::new(slots),)* }
+            }
+
+            fn clear_slot(&mut self, entity: Entity) {
+                $(self.$field.remove(entity);)*
+            }
+        }
+
+        $(impl Component for $ty {
+            fn storage(world: &mut World) -> &mut Storage<Self> {
+                &mut world.$field
+            }
+        })*
+    };
+}
+
+components! {
+    transforms: Transform,
+    velocities: Velocity,
+    colliders: Collider,
+    healths: Health,
+    lifetimes: Lifetime,
+    renderables: Renderable,
+    teams: Team,
+}
+
+impl World {
+    /// A fresh entity with no components, or `None` if `group` is full and
+    /// refuses newcomers. A full recycling group reuses its oldest entity.
+    pub fn spawn(&mut self, group: Group) -> Option<Builder<'_>> {
+        let (start, pool) = &mut self.groups[group as usize];
+        let handle = pool.insert()?;
+        let entity = Entity { index: *start + handle.index(), group, handle };
+        // The slot may have been recycled from a live entity
+        self.clear_slot(entity);
+        Some(Builder { world: self, entity })
+    }
+
+    /// Remove `entity` and all its components; a no-op if it's already gone
+    pub fn despawn(&mut self, entity: Entity) {
+        if self.groups[entity.group as usize].1.remove(entity.handle) {
+            self.clear_slot(entity);
+        }
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.groups[entity.group as usize].1.contains(entity.handle)
+    }
+
+    pub fn insert<C: Component>(&mut self, entity: Entity, component: C) {
+        C::storage(self).insert(entity, component);
+    }
+
+    /// Every slot number. Systems walk this range and look each slot up with
+    /// `entity`, which leaves them free to spawn and despawn as they go.
+    pub fn slots(&self) -> std::ops::Range<u32> {
+        let (start, pool) = &self.groups[self.groups.len() - 1];
+        0..start + pool.capacity() as u32
+    }
+
+    /// The entity in slot `index`, if it's live
+    pub fn entity(&self, index: u32) -> Option<Entity> {
+        let group = Group::ALL.into_iter().rev().find(|&g| self.groups[g as usize].0 <= index)?;
+        let (start, pool) = &self.groups[group as usize];
+        let handle = pool.handle_at(index - start)?;
+        Some(Entity { index, group, handle })
+    }
+
+    /// Live entities in slot order
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.slots().filter_map(|index| self.entity(index))
+    }
+
+    pub fn len(&self, group: Group) -> usize {
+        self.groups[group as usize].1.len()
+    }
+
+    pub fn capacity(&self, group: Group) -> usize {
+        self.groups[group as usize].1.capacity()
+    }
+
+    /// Despawn everything
+    pub fn clear(&mut self) {
+        for index in self.slots() {
+            if let Some(entity) = self.entity(index) {
+                self.despawn(entity);
+            }
+        }
+    }
+}
+
+/// Attaches components to a freshly spawned entity
+pub struct Builder<'a> {
+    world: &'a mut World,
+    entity: Entity,
+}
+
+impl Builder<'_> {
+    pub fn with<C: Component>(self, component: C) -> Self {
+        self.world.insert(self.entity, component);
+        self
+    }
+
+    pub fn id(self) -> Entity {
+        self.entity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small() -> World {
+        World::new(Capacities { particles: 4, projectiles: 2, threats: 2 })
+    }
+
+    #[test]
+    fn despawn_takes_every_component_with_it() {
+        let mut world = small();
+        let e = world
+            .spawn(Group::Threats)
+            .unwrap()
+            .with(Transform { x: 1.0, y: 2.0 })
+            .with(Health { hp: 3 })
+            .with(Team::Enemy)
+            .id();
+        assert_eq!(world.healths.get(e), Some(&Health { hp: 3 }));
+        world.despawn(e);
+        assert!(!world.contains(e));
+
+        // The same slot comes back bare, and the old id doesn't reach it
+        let fresh = world.spawn(Group::Threats).unwrap().id();
+        assert_eq!(fresh.index(), e.index());
+        assert_ne!(fresh, e);
+        assert!(!world.transforms.contains(fresh) && !world.teams.contains(fresh));
+        world.despawn(e);
+        assert!(world.contains(fresh));
+    }
+
+    #[test]
+    fn groups_keep_their_own_budgets() {
+        let mut world = small();
+        for _ in 0..10 {
+            world.spawn(Group::Particles).unwrap().with(Lifetime::new(1.0));
+        }
+        assert_eq!(world.len(Group::Particles), 4);
+        assert!(world.spawn(Group::Threats).is_some());
+        assert!(world.spawn(Group::Threats).is_some());
+        assert!(world.spawn(Group::Threats).is_none(), "threats refuse when full");
+        assert_eq!(world.iter().count(), 6);
+    }
+
+    #[test]
+    fn slots_map_back_to_their_entities() {
+        let mut world = small();
+        let spawned: Vec<Entity> = Group::ALL.iter().map(|&g| world.spawn(g).unwrap().id()).collect();
+        for e in &spawned {
+            assert_eq!(world.entity(e.index()), Some(*e));
+        }
+        // Groups are laid out in order, so iteration visits them in order too
+        assert_eq!(world.iter().collect::<Vec<_>>(), spawned);
+        world.clear();
+        assert_eq!(world.iter().count(), 0);
+        assert_eq!(world.slots(), 0..8);
+    }
+}
@@ -1,7 +1,16 @@
+pub mod achievements;
+pub mod broadphase;
+pub mod config;
+pub mod difficulty;
+pub mod ecs;
 pub mod entities;
+pub mod particles;
+pub mod pool;
 pub mod state;
+pub mod stats;
 pub mod systems;
 pub mod physics;
 
 pub use entities::*;
-pub use state::GameState;
\ No newline at end of file
+pub use config::GameConfig;
+pub use state::{Capacities, GameState};
\ No newline at end of file
@@ -0,0 +1,247 @@
+//! Particle effects, defined as presets in `particles.toml` (embedded at compile time)
+//!
+//! A particle is an entity with a transform, velocity, lifetime and a
+//! `Renderable::Particle`. The simulation only steers and slows them (the
+//! shared systems move and age them); what a particle looks like at a given
+//! age (size, opacity, colour) is read from its preset's curves when it is
+//! drawn.
+
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use crate::constants::*;
+use crate::game::ecs::{Group, Lifetime, Renderable, Transform, Velocity, World};
+use crate::game::state::GameState;
+use crate::rng::Rng;
+
+static PRESETS: Lazy<HashMap<Effect, Preset>> = Lazy::new(|| {
+    #[derive(Deserialize)]
+    struct File {
+        effects: HashMap<Effect, Preset>,
+    }
+    let file: File = toml::from_str(include_str!("particles.toml")).expect("game/particles.toml is invalid");
+    file.effects
+});
+
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    Explosion,
+    Debris,
+    Sparks,
+    EngineTrail,
+    ShieldHit,
+    Smoke,
+}
+
+impl Effect {
+    pub const ALL: [Effect; 6] = [
+        Effect::Explosion,
+        Effect::Debris,
+        Effect::Sparks,
+        Effect::EngineTrail,
+        Effect::ShieldHit,
+        Effect::Smoke,
+    ];
+
+    pub fn preset(self) -> &'static Preset {
+        PRESETS.get(&self).unwrap_or(&FALLBACK)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Preset {
+    /// Particles per burst
+    #[serde(default)]
+    pub burst: u32,
+    /// Particles per second from a continuous emitter
+    #[serde(default)]
+    pub rate: f64,
+    pub lifetime: [f64; 2],
+    pub speed: [f64; 2],
+    pub spread: f64,
+    pub drag: f64,
+    #[serde(default)]
+    pub gravity: f64,
+    #[serde(default)]
+    pub additive: bool,
+    pub size: Vec<(f32, f32)>,
+    pub alpha: Vec<(f32, f32)>,
+    pub color: Vec<(f32, u32)>,
+}
+
+static FALLBACK: Preset = Preset {
+    burst: 0,
+    rate: 0.0,
+    lifetime: [0.5, 0.5],
+    speed: [1.0, 1.0],
+    spread: TAU / 2.0,
+    drag: 0.98,
+    gravity: 0.0,
+    additive: false,
+    size: Vec::new(),
+    alpha: Vec::new(),
+    color: Vec::new(),
+};
+
+impl Preset {
+    /// Size in world units at `life` (0 at birth, 1 at death)
+    pub fn size_at(&self, life: f32) -> f32 {
+        sample(&self.size, life, 2.0, |a, b, t| a + (b - a) * t)
+    }
+
+    pub fn alpha_at(&self, life: f32) -> f32 {
+        sample(&self.alpha, life, 1.0, |a, b, t| a + (b - a) * t)
+    }
+
+    pub fn color_at(&self, life: f32) -> (u8, u8, u8) {
+        let rgb = sample(&self.color, life, 0xffffff, |a, b, t| {
+            let channel = |shift: u32| {
+                let (a, b) = (((a >> shift) & 0xff) as f32, ((b >> shift) & 0xff) as f32);
+                ((a + (b - a) * t).round() as u32) << shift
+            };
+            channel(16) | channel(8) | channel(0)
+        });
+        ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+}
+
+/// Piecewise-linear lookup in `keys` (sorted by life), held flat past either end
+fn sample<T: Copy>(keys: &[(f32, T)], life: f32, default: T, lerp: impl Fn(T, T, f32) -> T) -> T {
+    let Some(&(first_t, first)) = keys.first() else { return default };
+    if life <= first_t {
+        return first;
+    }
+    for pair in keys.windows(2) {
+        let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+        if life <= t1 {
+            let span = (t1 - t0).max(f32::EPSILON);
+            return lerp(v0, v1, (life - t0) / span);
+        }
+    }
+    keys[keys.len() - 1].1
+}
+
+/// Continuous source such as the engine trail; carries the fractional
+/// particle owed between ticks so low rates still come out even
+#[derive(Clone, Copy, Debug)]
+pub struct Emitter {
+    pub effect: Effect,
+    owed: f64,
+}
+
+impl Emitter {
+    pub fn new(effect: Effect) -> Self {
+        Emitter { effect, owed: 0.0 }
+    }
+}
+
+/// One burst of `effect` at (`x`, `y`), aimed along `direction` (radians).
+/// Takes the world and rng rather than the whole state so systems can emit
+/// while they are borrowing other parts of it.
+pub fn burst(world: &mut World, rng: &mut Rng, effect: Effect, x: f64, y: f64, direction: f64) {
+    spawn(world, rng, effect, x, y, direction, effect.preset().burst);
+}
+
+fn spawn(world: &mut World, rng: &mut Rng, effect: Effect, x: f64, y: f64, direction: f64, count: u32) {
+    let preset = effect.preset();
+    for _ in 0..count {
+        let angle = direction + rng.signed() * preset.spread;
+        let speed = rng.range(preset.speed[0], preset.speed[1]);
+        let lifetime = rng.range(preset.lifetime[0], preset.lifetime[1]);
+        let size = rng.range(0.75, 1.25);
+        if let Some(particle) = world.spawn(Group::Particles) {
+            particle
+                .with(Transform { x, y })
+                .with(Velocity { x: angle.cos() * speed, y: angle.sin() * speed })
+                .with(Lifetime::new(lifetime))
+                .with(Renderable::Particle { effect, size });
+        }
+    }
+}
+
+impl GameState {
+    /// One burst of `effect` at (`x`, `y`), aimed along `direction` (radians)
+    pub fn emit(&mut self, effect: Effect, x: f64, y: f64, direction: f64) {
+        burst(&mut self.world, &mut self.rng, effect, x, y, direction);
+    }
+
+    /// Run `emitter` for `dt` seconds at (`x`, `y`)
+    pub fn run_emitter(&mut self, emitter: &mut Emitter, x: f64, y: f64, direction: f64, dt: f64) {
+        emitter.owed += emitter.effect.preset().rate * dt;
+        let count = emitter.owed.floor();
+        emitter.owed -= count;
+        spawn(&mut self.world, &mut self.rng, emitter.effect, x, y, direction, count as u32);
+    }
+
+    /// Pull toward the planet and drag, per preset; runs before movement
+    pub(crate) fn steer_particles(&mut self, _dt: f64) {
+        let (cx, cy) = (WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        let world = &mut self.world;
+        for index in world.slots() {
+            let Some(entity) = world.entity(index) else { continue };
+            let Some(&Renderable::Particle { effect, .. }) = world.renderables.get(entity) else { continue };
+            let (Some(at), Some(velocity)) = (world.transforms.get(entity), world.velocities.get_mut(entity)) else { continue };
+            let preset = effect.preset();
+            if preset.gravity != 0.0 {
+                let (dx, dy) = (cx - at.x, cy - at.y);
+                let dist = (dx * dx + dy * dy).sqrt().max(1.0);
+                velocity.x += dx / dist * preset.gravity;
+                velocity.y += dy / dist * preset.gravity;
+            }
+            velocity.x *= preset.drag;
+            velocity.y *= preset.drag;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_effect_has_a_preset() {
+        for effect in Effect::ALL {
+            assert!(PRESETS.contains_key(&effect), "{effect:?} missing from particles.toml");
+        }
+    }
+
+    #[test]
+    fn curves_are_sorted_and_span_the_lifetime() {
+        for (effect, preset) in PRESETS.iter() {
+            let lifes = [
+                preset.size.iter().map(|k| k.0).collect::<Vec<_>>(),
+                preset.alpha.iter().map(|k| k.0).collect(),
+                preset.color.iter().map(|k| k.0).collect(),
+            ];
+            for keys in lifes {
+                assert!(!keys.is_empty(), "{effect:?}: empty curve");
+                assert!(keys.windows(2).all(|w| w[0] <= w[1]), "{effect:?}: keys out of order {keys:?}");
+                assert!(keys.iter().all(|t| (0.0..=1.0).contains(t)), "{effect:?}: key outside 0..1 {keys:?}");
+            }
+            assert!(preset.lifetime[0] > 0.0 && preset.lifetime[0] <= preset.lifetime[1], "{effect:?}: bad lifetime");
+            assert!(preset.burst > 0 || preset.rate > 0.0, "{effect:?}: emits nothing");
+        }
+    }
+
+    #[test]
+    fn gradient_interpolates_per_channel() {
+        let preset = Preset {
+            burst: 1,
+            rate: 0.0,
+            lifetime: [1.0, 1.0],
+            speed: [1.0, 1.0],
+            spread: 0.0,
+            drag: 1.0,
+            gravity: 0.0,
+            additive: false,
+            size: Vec::new(),
+            alpha: Vec::new(),
+            color: vec![(0.0, 0xff0000), (1.0, 0x0000ff)],
+        };
+        assert_eq!(preset.color_at(-1.0), (255, 0, 0));
+        assert_eq!(preset.color_at(0.5), (128, 0, 128));
+        assert_eq!(preset.color_at(2.0), (0, 0, 255));
+    }
+}
@@ -1,20 +1,103 @@
-use crate::game::entities::*;
 use crate::constants::*;
 
+/// Circles overlap; compares squared distances, so no `sqrt`
 pub fn check_collision(x1: f64, y1: f64, r1: f64, x2: f64, y2: f64, r2: f64) -> bool {
     let dx = x1 - x2;
     let dy = y1 - y2;
-    let distance = (dx * dx + dy * dy).sqrt();
-    distance < r1 + r2
+    let reach = r1 + r2;
+    dx * dx + dy * dy < reach * reach
 }
 
-pub fn is_threat_hitting_planet(threat: &Threat) -> bool {
-    let dist_to_center = ((threat.x - CANVAS_WIDTH/2.0).powi(2) + 
-                         (threat.y - CANVAS_HEIGHT/2.0).powi(2)).sqrt();
-    dist_to_center < PLANET_RADIUS
+/// Earliest moment a circle of radius `r1` moving from `start` by `motion`
+/// touches a stationary circle of radius `r2` at `center`, as a fraction
+/// 0..=1 of the move. 0 if they already overlap, `None` if they never meet.
+/// For two moving circles, pass the motion of one relative to the other.
+pub fn sweep_circles(start: (f64, f64), motion: (f64, f64), r1: f64, center: (f64, f64), r2: f64) -> Option<f64> {
+    // Solve |start + motion * t - center| = r1 + r2 for the smaller t
+    let (mx, my) = (start.0 - center.0, start.1 - center.1);
+    let reach = r1 + r2;
+    let c = mx * mx + my * my - reach * reach;
+    if c < 0.0 {
+        return Some(0.0);
+    }
+    let a = motion.0 * motion.0 + motion.1 * motion.1;
+    let b = mx * motion.0 + my * motion.1;
+    if a == 0.0 || b >= 0.0 {
+        // Standing still, or moving away
+        return None;
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / a;
+    (t <= 1.0).then_some(t)
+}
+
+pub fn is_hitting_planet(x: f64, y: f64) -> bool {
+    let dist_sq = (x - WORLD_WIDTH/2.0).powi(2) + 
+                  (y - WORLD_HEIGHT/2.0).powi(2);
+    dist_sq < PLANET_RADIUS * PLANET_RADIUS
 }
 
 pub fn is_on_screen(x: f64, y: f64, margin: f64) -> bool {
-    x > -margin && x < CANVAS_WIDTH + margin && 
-    y > -margin && y < CANVAS_HEIGHT + margin
-}
\ No newline at end of file
+    x > -margin && x < WORLD_WIDTH + margin && 
+    y > -margin && y < WORLD_HEIGHT + margin
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_shot_hits_what_it_passes_through() {
+        // Steps from one side of a small threat to the other in one tick;
+        // neither endpoint overlaps it
+        let (start, motion) = ((0.0, 0.0), (100.0, 0.0));
+        assert!(!check_collision(0.0, 0.0, 2.0, 50.0, 0.0, 5.0));
+        assert!(!check_collision(100.0, 0.0, 2.0, 50.0, 0.0, 5.0));
+        let toi = sweep_circles(start, motion, 2.0, (50.0, 0.0), 5.0).expect("tunnelled through");
+        assert!((toi - 0.43).abs() < 1e-9, "touches at x = 43, got t = {toi}");
+    }
+
+    #[test]
+    fn near_miss_stays_a_miss() {
+        assert_eq!(sweep_circles((0.0, 0.0), (100.0, 0.0), 2.0, (50.0, 7.5), 5.0), None);
+        // Grazing exactly is a touch
+        assert!(sweep_circles((0.0, 0.0), (100.0, 0.0), 2.0, (50.0, 7.0), 5.0).is_some());
+    }
+
+    #[test]
+    fn target_out_of_reach_this_tick() {
+        // In line, but the move ends short of it
+        assert_eq!(sweep_circles((0.0, 0.0), (10.0, 0.0), 2.0, (50.0, 0.0), 5.0), None);
+        // Or behind, moving away
+        assert_eq!(sweep_circles((0.0, 0.0), (10.0, 0.0), 2.0, (-20.0, 0.0), 5.0), None);
+    }
+
+    #[test]
+    fn overlapping_at_the_start_is_an_immediate_hit() {
+        assert_eq!(sweep_circles((48.0, 0.0), (10.0, 0.0), 2.0, (50.0, 0.0), 5.0), Some(0.0));
+        assert_eq!(sweep_circles((48.0, 0.0), (0.0, 0.0), 2.0, (50.0, 0.0), 5.0), Some(0.0));
+    }
+
+    #[test]
+    fn standing_still_never_hits() {
+        assert_eq!(sweep_circles((0.0, 0.0), (0.0, 0.0), 2.0, (50.0, 0.0), 5.0), None);
+    }
+
+    #[test]
+    fn crossing_paths_meet_in_relative_motion() {
+        // Shot heading right, threat heading down across its path; at the
+        // ends of the tick they're far apart, but they cross mid-move
+        let (shot, shot_v) = ((0.0, 0.0), (40.0, 0.0));
+        let (threat, threat_v) = ((20.0, -20.0), (0.0, 40.0));
+        let relative = (shot_v.0 - threat_v.0, shot_v.1 - threat_v.1);
+        let toi = sweep_circles(shot, relative, 1.0, threat, 1.0).expect("paths cross");
+        let hit = (shot.0 + shot_v.0 * toi, shot.1 + shot_v.1 * toi);
+        let at = (threat.0 + threat_v.0 * toi, threat.1 + threat_v.1 * toi);
+        assert!(((hit.0 - at.0).hypot(hit.1 - at.1) - 2.0).abs() < 1e-9, "should just touch at impact");
+        // The same shot against a threat that doesn't move is clear
+        assert_eq!(sweep_circles(shot, shot_v, 1.0, threat, 1.0), None);
+    }
+}
@@ -0,0 +1,211 @@
+//! Fixed-capacity slot allocator
+//!
+//! A `Pool` hands out slot numbers from a range fixed up front, from a free
+//! list, so spawning and despawning in the middle of a game never touches
+//! the allocator. It holds no data itself: the ECS keeps components in
+//! columns indexed by slot and asks the pool which slots are live. Slots
+//! carry a generation, bumped on every reuse, so a `Handle` to a dead
+//! entity can't see whatever moved into its slot.
+//!
+//! When a pool is full, `Overflow` decides what gives: cosmetic things
+//! like particles recycle their oldest member, gameplay things refuse
+//! the newcomer. Live slots are threaded oldest to newest on an intrusive
+//! list, so finding the oldest is O(1) however large the pool.
+
+/// End of the birth-order list
+const NONE: u32 = u32::MAX;
+
+/// Slot index plus the generation it was issued for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: u32,
+    generation: u32,
+}
+
+impl Handle {
+    pub fn index(self) -> u32 {
+        self.index
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Evict the longest-lived slot to make room
+    RecycleOldest,
+    /// Leave the pool as it is; `insert` returns `None`
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Slot {
+    generation: u32,
+    live: bool,
+    /// Neighbours in birth order while live
+    older: u32,
+    newer: u32,
+}
+
+#[derive(Debug)]
+pub struct Pool {
+    slots: Vec<Slot>,
+    /// Indices of empty slots; popped from the end
+    free: Vec<u32>,
+    overflow: Overflow,
+    /// Ends of the birth-order list of live slots
+    oldest: u32,
+    newest: u32,
+}
+
+impl Pool {
+    pub fn new(capacity: usize, overflow: Overflow) -> Self {
+        let slots = vec![Slot { generation: 0, live: false, older: NONE, newer: NONE }; capacity];
+        // Reversed so the low slots fill first and iteration stays compact
+        let free = (0..capacity as u32).rev().collect();
+        Pool { slots, free, overflow, oldest: NONE, newest: NONE }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.len() == self.slots.len()
+    }
+
+    /// Claim a slot, recycling or refusing per the overflow policy when full.
+    /// A recycled slot's old handle stops matching.
+    pub fn insert(&mut self) -> Option<Handle> {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None if self.overflow == Overflow::RecycleOldest && self.oldest != NONE => {
+                let oldest = self.oldest;
+                self.unlink(oldest);
+                oldest
+            }
+            None => return None,
+        };
+        self.link_newest(index);
+        let slot = &mut self.slots[index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.live = true;
+        Some(Handle { index, generation: slot.generation })
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.slots
+            .get(handle.index as usize)
+            .is_some_and(|slot| slot.live && slot.generation == handle.generation)
+    }
+
+    /// Free `handle`'s slot; false if it was already gone
+    pub fn remove(&mut self, handle: Handle) -> bool {
+        if !self.contains(handle) {
+            return false;
+        }
+        self.slots[handle.index as usize].live = false;
+        self.unlink(handle.index);
+        self.free.push(handle.index);
+        true
+    }
+
+    /// Handle to whatever is live in slot `index`
+    pub fn handle_at(&self, index: u32) -> Option<Handle> {
+        let slot = self.slots.get(index as usize)?;
+        slot.live.then_some(Handle { index, generation: slot.generation })
+    }
+
+    /// Append slot `index` to the newest end of the birth order
+    fn link_newest(&mut self, index: u32) {
+        let slot = &mut self.slots[index as usize];
+        slot.older = self.newest;
+        slot.newer = NONE;
+        match self.newest {
+            NONE => self.oldest = index,
+            newest => self.slots[newest as usize].newer = index,
+        }
+        self.newest = index;
+    }
+
+    /// Take slot `index` out of the birth order
+    fn unlink(&mut self, index: u32) {
+        let Slot { older, newer, .. } = self.slots[index as usize];
+        match older {
+            NONE => self.oldest = newer,
+            older => self.slots[older as usize].newer = newer,
+        }
+        match newer {
+            NONE => self.newest = older,
+            newer => self.slots[newer as usize].older = older,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live(pool: &Pool) -> Vec<u32> {
+        (0..pool.capacity() as u32).filter(|&i| pool.handle_at(i).is_some()).collect()
+    }
+
+    #[test]
+    fn slots_are_reused_without_growing() {
+        let mut pool = Pool::new(4, Overflow::Reject);
+        for _ in 0..10 {
+            let handles: Vec<_> = (0..4).map(|_| pool.insert().unwrap()).collect();
+            assert_eq!(pool.insert(), None, "full pool must refuse");
+            for handle in handles.iter().step_by(2) {
+                assert!(pool.remove(*handle));
+            }
+            assert_eq!(pool.len(), 2);
+            for handle in handles {
+                pool.remove(handle);
+            }
+            assert!(pool.is_empty());
+        }
+        assert_eq!(pool.capacity(), 4);
+    }
+
+    #[test]
+    fn stale_handles_miss() {
+        let mut pool = Pool::new(1, Overflow::Reject);
+        let first = pool.insert().unwrap();
+        assert!(pool.remove(first));
+        let second = pool.insert().unwrap();
+        assert_eq!(first.index(), second.index());
+        assert!(!pool.contains(first));
+        assert!(!pool.remove(first));
+        assert!(pool.contains(second));
+        assert_eq!(pool.handle_at(second.index()), Some(second));
+    }
+
+    #[test]
+    fn full_pool_recycles_oldest_first() {
+        let mut pool = Pool::new(3, Overflow::RecycleOldest);
+        let [a, b, c] = [(); 3].map(|()| pool.insert().unwrap());
+        let d = pool.insert().unwrap();
+        assert_eq!(d.index(), a.index());
+        assert!(!pool.contains(a));
+        let e = pool.insert().unwrap();
+        assert_eq!(e.index(), b.index());
+        assert!(pool.contains(c) && pool.contains(d) && pool.contains(e));
+    }
+
+    #[test]
+    fn removals_keep_the_birth_order() {
+        let mut pool = Pool::new(4, Overflow::RecycleOldest);
+        let [a, b, c, d] = [(); 4].map(|()| pool.insert().unwrap());
+        // Free the middle and the oldest, then refill past capacity
+        pool.remove(b);
+        pool.remove(a);
+        let (e, f) = (pool.insert().unwrap(), pool.insert().unwrap());
+        let g = pool.insert().unwrap();
+        assert_eq!(g.index(), c.index(), "c was the oldest left");
+        assert!(pool.contains(d) && pool.contains(e) && pool.contains(f));
+        assert_eq!(live(&pool), [0, 1, 2, 3]);
+    }
+}
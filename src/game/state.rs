@@ -1,40 +1,131 @@
 //! src/game/state.rs
-use crate::game::entities::{Player, Threat, Projectile, Particle};
-use crate::constants::*;
+use crate::game::broadphase::SpatialGrid;
+use crate::game::achievements::{self, Toast, ToastQueue, Unlocked};
+use crate::game::config::GameConfig;
+use crate::game::difficulty::{self, Adaptive, Difficulty};
+use crate::game::ecs::{Collider, Group, Health, Renderable, Team, Transform, Velocity, World};
+use crate::game::entities::Player;
+use crate::game::particles::{Effect, Emitter};
+use crate::game::stats::RunStats;
 use crate::audio::{self, Sound};
-use wasm_bindgen::prelude::*;
-use web_sys::console;
+use crate::rng::Rng;
 
 pub struct GameState {
+    /// Balance numbers, fixed for the life of a game
+    pub config: GameConfig,
+    /// Preset scaling the config; see `scaling`
+    pub difficulty: Difficulty,
+    pub adaptive: Adaptive,
     pub player: Player,
-    pub threats: Vec<Threat>,
-    pub projectiles: Vec<Projectile>,
-    pub particles: Vec<Particle>,
+    /// Threats, projectiles and particles
+    pub world: World,
+    /// Threats by position, rebuilt each tick for projectile hits
+    pub broadphase: SpatialGrid,
+    /// Exhaust behind the ship, running continuously
+    pub engine_trail: Emitter,
     pub score: u32,
+    pub stats: RunStats,
+    /// `stats` as the current wave began, for goals judged per wave
+    pub wave_start_stats: RunStats,
+    /// Achievements earned, in this game or (for `new`) saved from earlier ones
+    pub unlocked: Unlocked,
+    /// The unlock being announced, and those waiting their turn
+    pub toast: Option<Toast>,
+    pub pending_toasts: ToastQueue,
     pub planet_health: i32,
     pub time: f64,
     pub game_over: bool,
     pub combo: u32,
     pub combo_timer: f64,
+    /// Camera trauma; the renderer turns it into shake
     pub screen_shake: f64,
+    /// 0..1, decaying; the camera zooms in by this much on high-value kills
+    pub zoom_punch: f64,
     pub threat_spawn_timer: f64,
+    pub wave: u32,
+    /// Threats destroyed since the current wave began
+    pub wave_kills: u32,
+    /// Counts down while the "wave cleared" banner is shown
+    pub wave_banner_timer: f64,
+    /// All gameplay randomness; seed it for reproducible runs
+    pub rng: Rng,
+}
+
+/// Entity budgets per group, fixed for the life of a game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capacities {
+    /// Past this, the oldest particles are recycled for new ones
+    pub particles: usize,
+    /// Past this, the oldest shot in flight is dropped for a new one
+    pub projectiles: usize,
+    /// Past this, spawning waits until a threat dies
+    pub threats: usize,
+}
+
+impl Default for Capacities {
+    fn default() -> Self {
+        Capacities { particles: 2048, projectiles: 256, threats: 128 }
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new(GameConfig::default())
+    }
 }
 
 impl GameState {
-    pub fn new() -> Self {
+    /// A game at the player's chosen difficulty, with their achievements
+    /// so far
+    pub fn new(config: GameConfig) -> Self {
+        let mut state = Self::with_rng(Rng::from_entropy(), config);
+        state.set_difficulty(difficulty::settings());
+        state.unlocked = achievements::progress();
+        state
+    }
+
+    /// Same seed and same inputs give the same game, tick for tick; uses
+    /// the default config on normal difficulty
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(Rng::new(seed), GameConfig::default())
+    }
+
+    /// `with_seed`, with pool sizes other than the default
+    pub fn with_capacities(seed: u64, capacities: Capacities) -> Self {
+        let mut state = Self::with_seed(seed);
+        state.world = World::new(capacities);
+        state.broadphase = SpatialGrid::with_capacity(capacities.threats);
+        state
+    }
+
+    fn with_rng(rng: Rng, config: GameConfig) -> Self {
+        let capacities = Capacities::default();
         Self {
-            player: Player::new(),
-            threats: Vec::new(),
-            projectiles: Vec::new(),
-            particles: Vec::new(),
+            difficulty: Difficulty::Normal,
+            adaptive: Adaptive::new(false),
+            player: Player { speed: config.player.idle_speed, ..Player::new() },
+            world: World::new(capacities),
+            broadphase: SpatialGrid::with_capacity(capacities.threats),
+            engine_trail: Emitter::new(Effect::EngineTrail),
             score: 0,
-            planet_health: 100,
+            stats: RunStats::default(),
+            wave_start_stats: RunStats::default(),
+            unlocked: Unlocked::default(),
+            toast: None,
+            pending_toasts: ToastQueue::default(),
+            planet_health: config.planet.health,
             time: 0.0,
             game_over: false,
             combo: 0,
             combo_timer: 0.0,
             screen_shake: 0.0,
-            threat_spawn_timer: 2.0,
+            zoom_punch: 0.0,
+            threat_spawn_timer: config.threats.first_spawn_secs,
+            wave: 1,
+            wave_kills: 0,
+            wave_banner_timer: 0.0,
+            rng,
+            config,
         }
     }
 
@@ -44,43 +135,33 @@ impl GameState {
         if self.game_over { return; }
 
         let (x, y) = self.player.get_position();
-        let speed  = 8.0;
-        self.projectiles.push(Projectile {
-            x,
-            y,
-            vx: self.player.angle.cos() * speed,
-            vy: self.player.angle.sin() * speed,
-            radius: 5.0,
-        });
+        let speed  = self.config.projectile.speed;
+        // A full pool recycles the oldest shot in flight, which can't land now
+        let evicts = self.world.len(Group::Projectiles) == self.world.capacity(Group::Projectiles);
+        if let Some(shot) = self.world.spawn(Group::Projectiles) {
+            shot.with(Transform { x, y })
+                .with(Velocity { x: self.player.angle.cos() * speed, y: self.player.angle.sin() * speed })
+                .with(Collider { radius: self.config.projectile.radius })
+                .with(Health { hp: 1 })
+                .with(Team::Player)
+                .with(Renderable::Projectile);
+            self.adaptive.record_shot();
+            self.stats.shots_fired += 1;
+            if evicts {
+                self.stats.record_miss();
+            }
+        }
 
-        console::log_1(&format!("Shot fired! Projectiles: {}", self.projectiles.len()).into());
-        audio::play(Sound::Shoot);
+        log::trace!("shot at ({x:.0}, {y:.0}), {} in flight", self.world.len(Group::Projectiles));
+        audio::play_at(Sound::Shoot, x, y);
     }
 
-    pub fn create_explosion(&mut self, x: f64, y: f64,
-                             color: (u8, u8, u8), count: u32) {
-        for _ in 0..count {
-            let ang   = js_sys::Math::random() * std::f64::consts::TAU;
-            let speed = js_sys::Math::random() * 3.0 + 1.5;
-            self.particles.push(Particle {
-                x,
-                y,
-                vx: ang.cos() * speed,
-                vy: ang.sin() * speed,
-                size: js_sys::Math::random() * 3.0 + 1.0,
-                lifetime: 1.0,
-                max_lifetime: 1.0,
-                color,
-            });
-        }
-        audio::play(Sound::Explosion);
+    pub fn add_screen_shake(&mut self, intensity: f64) {
+        self.screen_shake = (self.screen_shake + intensity).min(self.config.shake.max);
     }
 
-    pub fn add_screen_shake(&mut self, intensity: f64) {
-        self.screen_shake = (self.screen_shake + intensity).min(15.0);
-        if intensity > 5.0 {
-            audio::play(Sound::PlanetHit);
-        }
+    pub fn add_zoom_punch(&mut self, amount: f64) {
+        self.zoom_punch = (self.zoom_punch + amount).min(1.0);
     }
 
     // REMOVED the duplicate update_player function from here
@@ -0,0 +1,171 @@
+//! Per-run statistics for the game-over summary and balance exports
+//!
+//! Systems bump the counters as things happen; anything the state already
+//! knows (score, time, wave) is read from it at report time rather than
+//! copied here.
+
+use serde::Serialize;
+use crate::game::config::ThreatConfig;
+use crate::game::state::GameState;
+use crate::settings::Choice;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct RunStats {
+    pub shots_fired: u32,
+    /// Shots that connected, whether or not they killed
+    pub hits: u32,
+    /// Shots that left the screen without touching anything, or were
+    /// recycled by a full projectile pool while still in flight
+    pub misses: u32,
+    /// Consecutive hits since the last miss
+    #[serde(skip)]
+    pub hit_streak: u32,
+    pub best_hit_streak: u32,
+    pub max_combo: u32,
+    pub threats_destroyed: u32,
+    /// `threats_destroyed` by `ThreatSize`
+    pub small_threats_destroyed: u32,
+    pub large_threats_destroyed: u32,
+    /// Planet damage from impacts, including any past the last point of health
+    pub damage_taken: i32,
+}
+
+impl RunStats {
+    /// 0..1; 0 before the first shot
+    pub fn accuracy(&self) -> f64 {
+        if self.shots_fired == 0 { 0.0 } else { self.hits as f64 / self.shots_fired as f64 }
+    }
+
+    pub fn record_hit(&mut self) {
+        self.hits += 1;
+        self.hit_streak += 1;
+        self.best_hit_streak = self.best_hit_streak.max(self.hit_streak);
+    }
+
+    pub fn record_miss(&mut self) {
+        self.misses += 1;
+        self.hit_streak = 0;
+    }
+
+    pub fn record_kill(&mut self, size: ThreatSize) {
+        self.threats_destroyed += 1;
+        match size {
+            ThreatSize::Small => self.small_threats_destroyed += 1,
+            ThreatSize::Large => self.large_threats_destroyed += 1,
+        }
+    }
+}
+
+/// Threats differ only in size, so size is their type in the stats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThreatSize {
+    Small,
+    Large,
+}
+
+impl ThreatSize {
+    /// Which half of the configured radius range `radius` falls in
+    pub fn of(radius: f64, config: &ThreatConfig) -> Self {
+        let [min, max] = config.radius;
+        if radius < (min + max) / 2.0 { ThreatSize::Small } else { ThreatSize::Large }
+    }
+}
+
+/// One finished (or abandoned) run, flat so it drops into a spreadsheet row
+#[derive(Serialize)]
+struct Report<'a> {
+    score: u32,
+    difficulty: &'a str,
+    adaptive: bool,
+    time_survived_secs: f64,
+    waves_cleared: u32,
+    accuracy: f64,
+    #[serde(flatten)]
+    stats: RunStats,
+}
+
+impl GameState {
+    pub fn waves_cleared(&self) -> u32 {
+        self.wave.saturating_sub(1)
+    }
+
+    /// The run as a JSON object
+    pub fn stats_json(&self) -> String {
+        let report = Report {
+            score: self.score,
+            difficulty: self.difficulty.label(),
+            adaptive: self.adaptive.enabled,
+            time_survived_secs: self.time,
+            waves_cleared: self.waves_cleared(),
+            accuracy: self.stats.accuracy(),
+            stats: self.stats,
+        };
+        serde_json::to_string_pretty(&report).expect("run stats always serialize")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::ecs::Group;
+    use crate::game::{Capacities, GameConfig};
+
+    #[test]
+    fn streaks_end_on_a_miss() {
+        let mut stats = RunStats { shots_fired: 5, ..RunStats::default() };
+        stats.record_hit();
+        stats.record_hit();
+        stats.record_miss();
+        stats.record_hit();
+        assert_eq!((stats.hits, stats.misses, stats.hit_streak, stats.best_hit_streak), (3, 1, 1, 2));
+        assert_eq!(stats.accuracy(), 0.6);
+        assert_eq!(RunStats::default().accuracy(), 0.0);
+    }
+
+    #[test]
+    fn kills_are_split_by_size() {
+        let config = GameConfig::default().threats;
+        let [min, max] = config.radius;
+        let mut stats = RunStats::default();
+        stats.record_kill(ThreatSize::of(min, &config));
+        stats.record_kill(ThreatSize::of(max, &config));
+        stats.record_kill(ThreatSize::of(max - 0.5, &config));
+        assert_eq!((stats.threats_destroyed, stats.small_threats_destroyed, stats.large_threats_destroyed), (3, 1, 2));
+    }
+
+    #[test]
+    fn shots_recycled_in_flight_are_misses() {
+        let mut state = GameState::with_capacities(3, Capacities { projectiles: 2, ..Capacities::default() });
+        for _ in 0..5 {
+            state.shoot();
+        }
+        assert_eq!(state.world.len(Group::Projectiles), 2);
+        assert_eq!((state.stats.shots_fired, state.stats.misses), (5, 3));
+    }
+
+    #[test]
+    fn a_played_run_adds_up() {
+        let mut state = GameState::with_seed(7);
+        let mut fired = 0;
+        for tick in 0..60 * 40 {
+            if tick % 20 == 0 && !state.game_over {
+                state.shoot();
+                fired += 1;
+            }
+            state.update(1.0 / 60.0);
+        }
+        let stats = state.stats;
+        assert_eq!(stats.shots_fired, fired);
+        assert!(stats.hits + stats.misses <= stats.shots_fired);
+        assert!(stats.threats_destroyed <= stats.hits);
+        assert_eq!(stats.small_threats_destroyed + stats.large_threats_destroyed, stats.threats_destroyed);
+        assert!(stats.best_hit_streak <= stats.hits);
+        assert!(stats.damage_taken >= state.config.planet.health - state.planet_health, "overkill counts too");
+
+        let json: serde_json::Value = serde_json::from_str(&state.stats_json()).unwrap();
+        assert_eq!(json["shots_fired"], fired);
+        assert_eq!(json["difficulty"], "NORMAL");
+        assert_eq!(json["large_threats_destroyed"], stats.large_threats_destroyed);
+        assert!(json.get("hit_streak").is_none());
+    }
+}
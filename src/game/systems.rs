@@ -1,25 +1,55 @@
 // src/game/systems.rs
 use crate::game::state::GameState;
-use crate::game::entities::{Threat}; 
+use crate::game::ecs::{Collider, Group, Health, Renderable, Team, Transform, Velocity, Entity, World};
+use crate::game::particles::{self, Effect};
 use crate::game::physics::*;
+use crate::game::stats::ThreatSize;
 use crate::constants::*;
-use crate::audio::{self, Sound};
+use crate::audio::{self, Sound, Stinger};
+
+/// A system runs once per tick over the whole game, given the tick length in seconds
+pub type System = fn(&mut GameState, f64);
+
+/// Every system, in the order they run each tick
+pub const SYSTEMS: [(&str, System); 14] = [
+    ("player", GameState::update_player),
+    ("engine_trail", GameState::update_engine_trail),
+    ("spawn_threats", GameState::spawn_threats),
+    ("steer_particles", GameState::steer_particles),
+    ("movement", GameState::movement),
+    ("planet_impacts", GameState::planet_impacts),
+    ("hits", GameState::resolve_hits),
+    ("cull", GameState::cull_off_screen),
+    ("age", GameState::age),
+    ("combo", GameState::update_combo_timer),
+    ("difficulty", GameState::adapt_difficulty),
+    ("achievements", GameState::check_achievements),
+    ("screen_shake", GameState::update_screen_shake),
+    ("timers", GameState::update_timers),
+];
+
+/// How long the "wave cleared" banner stays up
+const WAVE_BANNER_SECS: f64 = 3.0;
+
+
+/// Take a point of health off `entity`; true if that finished it
+fn damage(world: &mut World, entity: Entity) -> bool {
+    let Some(health) = world.healths.get_mut(entity) else { return false };
+    health.hp = health.hp.saturating_sub(1);
+    health.hp == 0
+}
 
 impl GameState {
    pub fn update(&mut self, dt: f64) {
         if self.game_over { return; }
         self.time += dt;
 
-        self.update_player();
-        self.spawn_threats(dt);
-        self.update_threats();
-        self.update_projectiles();
-        self.update_particles(dt);
-        self.update_combo_timer(dt);
-        self.update_screen_shake(dt);
+        for (_, system) in SYSTEMS {
+            system(self, dt);
+        }
     }
     
-    fn update_player(&mut self) {
+    fn update_player(&mut self, _dt: f64) {
         self.player.angle += self.player.speed;
         if self.player.angle >= std::f64::consts::PI * 2.0 {
             self.player.angle -= std::f64::consts::PI * 2.0;
@@ -28,116 +58,257 @@ impl GameState {
         }
     }
     
+    /// Exhaust from the back of the ship, streaming against its direction of travel
+    fn update_engine_trail(&mut self, dt: f64) {
+        let (x, y) = self.player.get_position();
+        let (cos, sin) = (self.player.angle.cos(), self.player.angle.sin());
+        let rear = (x - cos * PLAYER_SIZE / 2.0, y - sin * PLAYER_SIZE / 2.0);
+        let backwards = self.player.angle - std::f64::consts::FRAC_PI_2 * self.player.speed.signum();
+        let mut trail = self.engine_trail;
+        self.run_emitter(&mut trail, rear.0, rear.1, backwards, dt);
+        self.engine_trail = trail;
+    }
+
     fn spawn_threats(&mut self, delta: f64) {
         self.threat_spawn_timer -= delta;
-        if self.threat_spawn_timer <= 0.0 {
-            self.spawn_threat(); 
-            let initial_interval_secs = 2.0;
-            let min_interval_secs = 0.5;
-            let time_to_reach_min_rate_secs = 60.0;
-            let progression = (self.time / time_to_reach_min_rate_secs).min(1.0);
-            let next_spawn_interval = initial_interval_secs - (initial_interval_secs - min_interval_secs) * progression;
-            self.threat_spawn_timer = next_spawn_interval.max(min_interval_secs);
+        // A full threat pool holds the spawn back until something dies
+        if self.threat_spawn_timer <= 0.0 && self.spawn_threat() {
+            self.threat_spawn_timer = self.spawn_interval();
         }
     }
     
-    fn spawn_threat(&mut self) {
-        let angle = js_sys::Math::random() * std::f64::consts::PI * 2.0;
-        let spawn_dist = CANVAS_WIDTH * 0.6;
-        let x = CANVAS_WIDTH / 2.0 + angle.cos() * spawn_dist;
-        let y = CANVAS_HEIGHT / 2.0 + angle.sin() * spawn_dist;
-        let target_x = CANVAS_WIDTH / 2.0 + (js_sys::Math::random() - 0.5) * PLANET_RADIUS * 1.5;
-        let target_y = CANVAS_HEIGHT / 2.0 + (js_sys::Math::random() - 0.5) * PLANET_RADIUS * 1.5;
+    /// 0..1, how far spawning has sped up from its starting interval to its floor
+    pub fn spawn_progression(&self) -> f64 {
+        (self.time / self.config.waves.ramp_secs).min(1.0)
+    }
+
+    /// Seconds between spawns at the current point in the game, on the
+    /// current difficulty
+    pub fn spawn_interval(&self) -> f64 {
+        let [initial, floor] = self.config.waves.spawn_interval;
+        (initial - (initial - floor) * self.spawn_progression()).max(floor) / self.scaling().spawn_rate
+    }
+
+    /// Waves are kill milestones: spawning never stops, a wave is just
+    /// cleared once enough threats have been destroyed during it
+    fn clear_wave(&mut self) {
+        self.check_wave_achievements();
+        self.wave_kills -= self.config.threats_in_wave(self.wave);
+        self.wave += 1;
+        self.wave_banner_timer = WAVE_BANNER_SECS;
+        audio::play_stinger(Stinger::WaveClear);
+    }
+    
+    fn spawn_threat(&mut self) -> bool {
+        let angle = self.rng.range(0.0, std::f64::consts::PI * 2.0);
+        let x = WORLD_WIDTH / 2.0 + angle.cos() * THREAT_SPAWN_DISTANCE;
+        let y = WORLD_HEIGHT / 2.0 + angle.sin() * THREAT_SPAWN_DISTANCE;
+        let target_x = WORLD_WIDTH / 2.0 + self.rng.signed() * 0.5 * PLANET_RADIUS * 1.5;
+        let target_y = WORLD_HEIGHT / 2.0 + self.rng.signed() * 0.5 * PLANET_RADIUS * 1.5;
         let dx = target_x - x;
         let dy = target_y - y;
         let dist_to_target = (dx * dx + dy * dy).sqrt();
-        let threat_speed = 1.5 + js_sys::Math::random() * 1.0;
-        self.threats.push(Threat {
-            x, y,
-            vx: (dx / dist_to_target.max(0.1)) * threat_speed,
-            vy: (dy / dist_to_target.max(0.1)) * threat_speed,
-            radius: 12.0 + js_sys::Math::random() * 6.0,
-        });
+        let scaling = self.scaling();
+        let [min_speed, max_speed] = self.config.threats.speed;
+        let speed = self.rng.range(min_speed, max_speed) * scaling.threat_speed;
+        let [min_radius, max_radius] = self.config.threats.radius;
+        let radius = self.rng.range(min_radius, max_radius);
+        let Some(threat) = self.world.spawn(Group::Threats) else { return false };
+        threat
+            .with(Transform { x, y })
+            .with(Velocity { x: (dx / dist_to_target.max(0.1)) * speed, y: (dy / dist_to_target.max(0.1)) * speed })
+            .with(Collider { radius })
+            .with(Health { hp: scaling.threat_hp(1) })
+            .with(Team::Enemy)
+            .with(Renderable::Threat);
+        true
     }
     
- fn update_threats(&mut self) {
+    /// Everything with a velocity moves by it
+    fn movement(&mut self, _dt: f64) {
+        let world = &mut self.world;
+        for index in world.slots() {
+            let Some(entity) = world.entity(index) else { continue };
+            let (Some(at), Some(velocity)) = (world.transforms.get_mut(entity), world.velocities.get(entity)) else { continue };
+            at.x += velocity.x;
+            at.y += velocity.y;
+        }
+    }
+
+    /// Enemies that reach the planet hurt it and are gone
+    fn planet_impacts(&mut self, _dt: f64) {
         let mut planet_hit = false;
 
-        self.threats.retain_mut(|t| {
-            t.x += t.vx;
-            t.y += t.vy;
-            if is_threat_hitting_planet(t) {
-                self.planet_health = (self.planet_health - 10).max(0);
+        for index in self.world.slots() {
+            let Some(entity) = self.world.entity(index) else { continue };
+            if self.world.teams.get(entity) != Some(&Team::Enemy) {
+                continue;
+            }
+            let Some(&Transform { x, y }) = self.world.transforms.get(entity) else { continue };
+            if is_hitting_planet(x, y) {
+                let impact = self.scaling().planet_damage(self.config.planet.impact_damage);
+                self.planet_health = (self.planet_health - impact).max(0);
+                self.adaptive.record_damage(impact);
+                self.stats.damage_taken += impact;
                 planet_hit = true;
-                return false;
+                self.world.despawn(entity);
+                // Flare off the shield, away from the planet
+                let outward = (y - WORLD_HEIGHT / 2.0).atan2(x - WORLD_WIDTH / 2.0);
+                particles::burst(&mut self.world, &mut self.rng, Effect::ShieldHit, x, y, outward);
+                audio::play_at(Sound::PlanetHit, x, y);
             }
-            is_on_screen(t.x, t.y, t.radius + CANVAS_WIDTH * 0.2)
-        });
+        }
 
         if planet_hit {
-            self.add_screen_shake(6.0);
-            if self.planet_health == 0 { self.game_over = true; }
+            self.add_screen_shake(self.config.shake.planet_hit);
+            if self.planet_health == 0 {
+                self.game_over = true;
+                audio::play_stinger(Stinger::GameOver);
+            }
         }
     }
 
-    
-   fn update_projectiles(&mut self) {
-        // FIXED: Collect explosions first, then apply after retain
-        let mut explosions_to_create = Vec::new();
+    /// Player colliders against enemy colliders. Both sides take a point of
+    /// damage per hit, so a shot (one hit point) is spent on the first thing
+    /// it touches.
+    fn resolve_hits(&mut self, _dt: f64) {
         let mut score_to_add = 0;
-        
-        self.projectiles.retain_mut(|p| {
-            p.x += p.vx; 
-            p.y += p.vy;
-            
-            for t in &mut self.threats {
-                if check_collision(p.x, p.y, p.radius, t.x, t.y, t.radius) {
-                    t.radius = 0.0; // Mark for removal
-                    
-                    // FIXED: Add scoring logic here
-                    self.combo += 1;
-                    self.combo_timer = 2.0; // Reset combo timer
-                    let points = 10 * self.combo.min(10); // Max 10x multiplier
-                    score_to_add += points;
-                    
-                    // Store explosion data for later
-                    explosions_to_create.push((t.x, t.y, (255, 200, 100), 15));
-                    
-                    // Debug log
-                    web_sys::console::log_1(&format!("Hit! Score: {}, Combo: {}x", points, self.combo).into());
-                    
-                    return false;
+        let mut milestone = false;
+
+        let world = &self.world;
+        self.broadphase.rebuild(world.iter().filter_map(|e| {
+            if world.teams.get(e) != Some(&Team::Enemy) || !world.healths.contains(e) {
+                return None;
+            }
+            let (at, collider) = (world.transforms.get(e)?, world.colliders.get(e)?);
+            Some((e.index(), at.x, at.y, collider.radius))
+        }));
+
+        for index in self.world.slots() {
+            let Some(shot) = self.world.entity(index) else { continue };
+            if self.world.teams.get(shot) != Some(&Team::Player) || !self.world.healths.contains(shot) {
+                continue;
+            }
+            let (Some(&at), Some(&v), Some(&Collider { radius })) =
+                (self.world.transforms.get(shot), self.world.velocities.get(shot), self.world.colliders.get(shot))
+            else {
+                continue;
+            };
+            // Where it started this tick; movement has already run
+            let (x0, y0) = (at.x - v.x, at.y - v.y);
+
+            // Sweep the whole move rather than testing where it ends, so
+            // fast shots can't skip over small threats. Threats have moved
+            // too; work in their frame, from where they started.
+            let reach = radius + v.x.hypot(v.y) / 2.0 + self.config.max_threat_speed();
+            let mut first_hit = None;
+            for id in self.broadphase.query(x0 + v.x / 2.0, y0 + v.y / 2.0, reach) {
+                // Destroyed by an earlier shot this tick
+                let Some(target) = self.world.entity(id) else { continue };
+                let (Some(t), Some(c)) = (self.world.transforms.get(target), self.world.colliders.get(target)) else { continue };
+                let tv = self.world.velocities.get(target).copied().unwrap_or(Velocity { x: 0.0, y: 0.0 });
+                let relative = (v.x - tv.x, v.y - tv.y);
+                if let Some(toi) = sweep_circles((x0, y0), relative, radius, (t.x - tv.x, t.y - tv.y), c.radius) {
+                    if first_hit.is_none_or(|(best, _, _)| toi < best) {
+                        first_hit = Some((toi, target, tv));
+                    }
                 }
             }
-            is_on_screen(p.x, p.y, p.radius + 50.0)
-        });
-        
-        // Apply score after borrows are resolved
+
+            let Some((toi, target, tv)) = first_hit else { continue };
+            // Both at the moment of impact
+            let (hit_x, hit_y) = (x0 + v.x * toi, y0 + v.y * toi);
+            let Some(&Transform { x, y }) = self.world.transforms.get(target) else { continue };
+            let (tx, ty) = (x - tv.x * (1.0 - toi), y - tv.y * (1.0 - toi));
+
+            self.adaptive.record_hit();
+            self.stats.record_hit();
+            if damage(&mut self.world, shot) {
+                self.world.despawn(shot);
+            }
+            if !damage(&mut self.world, target) {
+                // Armour spark; nothing dies
+                particles::burst(&mut self.world, &mut self.rng, Effect::Sparks, hit_x, hit_y, (-v.y).atan2(-v.x));
+                continue;
+            }
+            let radius = self.world.colliders.get(target).map_or(0.0, |c| c.radius);
+            let size = ThreatSize::of(radius, &self.config.threats);
+            self.world.despawn(target);
+
+            // FIXED: Add scoring logic here
+            self.combo += 1;
+            self.stats.max_combo = self.stats.max_combo.max(self.combo);
+            self.stats.record_kill(size);
+            self.wave_kills += 1;
+            let combo = &self.config.combo;
+            // There are no bosses, so the high-value kill the camera punches
+            // in on is the first one worth the full combo multiplier
+            milestone |= self.combo == combo.max_multiplier;
+            self.combo_timer = combo.window_secs; // Reset combo timer
+            let points = combo.points * self.combo.min(combo.max_multiplier);
+            score_to_add += points;
+
+            let mut emit = |effect| particles::burst(&mut self.world, &mut self.rng, effect, tx, ty, 0.0);
+            emit(Effect::Explosion);
+            emit(Effect::Debris);
+            audio::play_at(Sound::Explosion, tx, ty);
+            log::debug!("kill at ({tx:.0}, {ty:.0}): +{points}, combo {}x", self.combo);
+        }
+
         self.score += score_to_add;
-        
-        // Remove destroyed threats
-        self.threats.retain(|t| t.radius > 0.0);
-        
-        // Create explosions after borrow issues are resolved
-        for (x, y, color, count) in explosions_to_create {
-            self.create_explosion(x, y, color, count);
+        if self.wave_kills >= self.config.threats_in_wave(self.wave) {
+            self.clear_wave();
+        }
+
+        if milestone {
+            self.add_zoom_punch(1.0);
         }
     }
-    
-    fn update_particles(&mut self, delta: f64) {
-        self.particles.retain_mut(|particle| {
-            particle.x += particle.vx; particle.y += particle.vy;
-            particle.vx *= 0.98; particle.vy *= 0.98; 
-            particle.lifetime -= delta * 2.0; 
-            particle.lifetime > 0.0
-        });
+
+    /// Colliders that have left the playfield. Enemies get a wide margin,
+    /// since they spawn off screen and fly in.
+    fn cull_off_screen(&mut self, _dt: f64) {
+        let cull_margin = self.config.threats.cull_margin;
+        let world = &mut self.world;
+        for index in world.slots() {
+            let Some(entity) = world.entity(index) else { continue };
+            let (Some(at), Some(collider), Some(team)) =
+                (world.transforms.get(entity), world.colliders.get(entity), world.teams.get(entity))
+            else {
+                continue;
+            };
+            let margin = match team {
+                Team::Enemy => collider.radius + cull_margin,
+                Team::Player => collider.radius + 50.0,
+            };
+            if !is_on_screen(at.x, at.y, margin) {
+                if *team == Team::Player {
+                    // Left without touching anything
+                    self.stats.record_miss();
+                }
+                world.despawn(entity);
+            }
+        }
     }
 
+    /// Count lifetimes down, despawning what runs out
+    fn age(&mut self, dt: f64) {
+        let world = &mut self.world;
+        for index in world.slots() {
+            let Some(entity) = world.entity(index) else { continue };
+            let Some(lifetime) = world.lifetimes.get_mut(entity) else { continue };
+            lifetime.remaining -= dt;
+            if lifetime.remaining <= 0.0 {
+                world.despawn(entity);
+            }
+        }
+    }
+    
     fn update_combo_timer(&mut self, delta: f64) {
         if self.combo_timer > 0.0 {
             self.combo_timer -= delta; 
             if self.combo_timer <= 0.0 { 
-                web_sys::console::log_1(&format!("Combo reset from {}x", self.combo).into());
+                log::debug!("combo reset from {}x", self.combo);
                 self.combo = 0; 
                 self.combo_timer = 0.0; 
             }
@@ -146,8 +317,13 @@ impl GameState {
 
     fn update_screen_shake(&mut self, delta: f64) {
         if self.screen_shake > 0.0 {
-            self.screen_shake -= delta * 5.0; 
+            self.screen_shake -= delta * self.config.shake.decay_per_sec;
             if self.screen_shake < 0.0 { self.screen_shake = 0.0; }
         }
     }
+
+    fn update_timers(&mut self, dt: f64) {
+        self.zoom_punch = (self.zoom_punch - dt * 1.5).max(0.0);
+        self.wave_banner_timer = (self.wave_banner_timer - dt).max(0.0);
+    }
 }
\ No newline at end of file
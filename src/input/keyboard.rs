@@ -2,54 +2,130 @@
 use web_sys::KeyboardEvent;
 use std::cell::RefCell;
 use std::rc::Rc;
+use crate::audio;
+use crate::display;
+use crate::game::difficulty;
 use crate::game::GameState;
-// If you use constants like PLAYER_SPEED_INCREMENT, ensure they are defined or imported.
-// For now, using the hardcoded values from your original code.
+use crate::ui::debug::DebugOverlay;
+use crate::ui::options::{step_volume, OptionsMenu};
+use crate::ui::summary::RunSummary;
 
 pub struct InputHandler {
-    game_state: Rc<RefCell<GameState>>, 
+    game_state: Rc<RefCell<GameState>>,
+    options: Rc<RefCell<OptionsMenu>>,
+    debug: Rc<RefCell<DebugOverlay>>,
+    summary: Rc<RefCell<RunSummary>>,
 }
 
 impl InputHandler {
-    pub fn new(game_state: Rc<RefCell<GameState>>) -> Self {
-        InputHandler { game_state }
+    pub fn new(
+        game_state: Rc<RefCell<GameState>>,
+        options: Rc<RefCell<OptionsMenu>>,
+        debug: Rc<RefCell<DebugOverlay>>,
+        summary: Rc<RefCell<RunSummary>>,
+    ) -> Self {
+        InputHandler { game_state, options, debug, summary }
     }
     
     pub fn handle_keydown(&self, event: KeyboardEvent) {
-        // Optional: Keep this log for now to see that this method is reached
-        // web_sys::console::log_1(&format!("InputHandler::handle_keydown - Key: {} - Applying game logic.", event.key()).into());
+        // Browsers only let audio start after a user gesture
+        audio::init_audio_context();
+
+        let key = event.key();
+        match key.as_str() {
+            "Escape" | "o" | "O" => {
+                self.options.borrow_mut().toggle();
+                return;
+            }
+            "m" | "M" => {
+                audio::toggle_mute();
+                return;
+            }
+            "f" | "F" => {
+                display::toggle_fullscreen();
+                return;
+            }
+            "F3" => {
+                // Otherwise the browser opens find-in-page
+                event.prevent_default();
+                self.debug.borrow_mut().toggle();
+                return;
+            }
+            "-" | "_" => {
+                audio::set_master(step_volume(audio::volume().master, -1.0));
+                return;
+            }
+            "=" | "+" => {
+                audio::set_master(step_volume(audio::volume().master, 1.0));
+                return;
+            }
+            _ => {}
+        }
+
+        let mut options = self.options.borrow_mut();
+        if options.achievements {
+            // Nothing to pick on the achievements screen; any of these go back
+            if matches!(key.as_str(), "Enter" | " " | "Backspace" | "ArrowLeft" | "a" | "A") {
+                options.toggle();
+            }
+            return;
+        }
+        if options.open {
+            match key.as_str() {
+                "ArrowUp" | "w" | "W" => options.move_selection(-1),
+                "ArrowDown" | "s" | "S" => options.move_selection(1),
+                "ArrowLeft" | "a" | "A" => options.adjust(-1.0),
+                "ArrowRight" | "d" | "D" => options.adjust(1.0),
+                "Enter" | " " => options.activate(),
+                _ => {}
+            }
+            // Difficulty changes take effect in the running game
+            self.game_state.borrow_mut().set_difficulty(difficulty::settings());
+            return;
+        }
 
-        // --- RESTORED GAME LOGIC ---
         let mut state = self.game_state.borrow_mut(); 
-        match event.key().as_str() {
+        if state.game_over {
+            let mut summary = self.summary.borrow_mut();
+            match key.as_str() {
+                "ArrowLeft" | "a" | "A" => summary.turn(-1),
+                "ArrowRight" | "d" | "D" => summary.turn(1),
+                "e" | "E" => summary.export(&state),
+                _ => {}
+            }
+            return;
+        }
+        let mut debug = self.debug.borrow_mut();
+        if debug.open {
+            match key.as_str() {
+                "[" => debug.move_selection(-1),
+                "]" => debug.move_selection(1),
+                "," => debug.adjust(&mut state.config, -1.0),
+                "." => debug.adjust(&mut state.config, 1.0),
+                _ => {}
+            }
+        }
+        match key.as_str() {
             "ArrowLeft" | "a" | "A" => {
-                state.player.speed = -0.03; // Or your defined constant for player speed
+                state.player.speed = -state.config.player.turn_speed;
             }
             "ArrowRight" | "d" | "D" => {
-                state.player.speed = 0.03; // Or your defined constant for player speed
+                state.player.speed = state.config.player.turn_speed;
             }
-            " " => { // Space bar
-                // The state.shoot() method itself should still have its internal
-                // audio_manager.play_sfx("shoot") call commented out for this specific test.
+            " " => {
                 state.shoot(); 
             }
             _ => {}
         }
-        // --- END OF RESTORED GAME LOGIC ---
     }
     
     pub fn handle_keyup(&self, event: KeyboardEvent) {
-        // Optional: Keep this log
-        // web_sys::console::log_1(&format!("InputHandler::handle_keyup - Key: {}", event.key()).into());
-
-        // --- RESTORED GAME LOGIC ---
         let mut state = self.game_state.borrow_mut();
         match event.key().as_str() {
             "ArrowLeft" | "a" | "A" | "ArrowRight" | "d" | "D" => {
-                state.player.speed = 0.0; // Stop player movement, or to a default idle/drift speed
+                state.player.speed = state.config.player.idle_speed;
             }
             _ => {}
         }
-        // --- END OF RESTORED GAME LOGIC ---
     }
-}
\ No newline at end of file
+}
@@ -2,53 +2,89 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, window};
 use std::f64;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 // Import your modular system
-mod constants;
-mod audio;
-mod game;
-mod input;
-mod rendering;
-
-use constants::*;
-use audio::Sound;
-use game::GameState;
-use input::InputHandler;
-use rendering::Renderer;
+pub mod constants;
+pub mod audio;
+pub mod display;
+pub mod game;
+pub mod input;
+pub mod logging;
+pub mod rendering;
+pub mod rng;
+pub mod settings;
+pub mod storage;
+pub mod ui;
 
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
+use audio::{MusicMood, Sound};
+use game::{config, GameConfig, GameState};
+use input::InputHandler;
+use rendering::canvas2d::Canvas2dBackend;
+use rendering::webgl::WebGl2Backend;
+use rendering::{RenderBackend, Renderer};
+use ui::{DebugOverlay, OptionsMenu, RunSummary};
 
-macro_rules! console_log {
-    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
+/// Everything the game loop shares with the event listeners
+#[derive(Clone, Default)]
+struct Shared {
+    state: Rc<RefCell<GameState>>,
+    options: Rc<RefCell<OptionsMenu>>,
+    debug: Rc<RefCell<DebugOverlay>>,
+    summary: Rc<RefCell<RunSummary>>,
+    /// The config file is still on its way
+    config_pending: Rc<Cell<bool>>,
+    /// The window changed size since the last frame
+    resized: Rc<Cell<bool>>,
 }
 
 // Main game loop - FIXED
-fn game_loop(
-    renderer: Rc<Renderer>,
-    state: Rc<RefCell<GameState>>,
-    last_time: Rc<RefCell<f64>>,
-) {
+fn game_loop(mut renderer: Renderer, canvas: HtmlCanvasElement, shared: Shared) {
+    let Shared { state, options, debug, summary, config_pending, resized } = shared;
+    let mut last_time = 0.0;
     let f = Rc::new(RefCell::new(None));
     let g = f.clone();
 
     *g.borrow_mut() = Some(Closure::wrap(Box::new(move |timestamp: f64| {
-        let delta = timestamp - *last_time.borrow();
-        *last_time.borrow_mut() = timestamp;
+        let delta = timestamp - last_time;
+        last_time = timestamp;
 
         // Convert milliseconds to seconds for game logic
         let dt = delta / 1000.0;
 
-        // Update game state - CRITICAL FIX
-        state.borrow_mut().update(dt);
+        // Resize events can come in bursts; only the last size matters
+        if resized.replace(false) {
+            match display::fit_canvas(&canvas) {
+                Ok((width, height)) => renderer.resize(width, height),
+                Err(err) => log::error!("Canvas resize failed: {:?}", err),
+            }
+        }
+
+        // Hold on the loading screen until every sample is decoded and the
+        // config file, if any, has arrived
+        let preload = audio::preload_progress();
+        if !preload.is_done() || config_pending.get() {
+            renderer.draw_loading(preload);
+            request_animation_frame(f.borrow().as_ref().unwrap());
+            return;
+        }
+
+        // Update game state - paused while the options menu is up
+        let options = options.borrow();
+        if !options.open {
+            state.borrow_mut().update(dt);
+        }
+        {
+            let state = state.borrow();
+            audio::set_music_mood(MusicMood::from_game(state.combo, state.planet_health));
+            game::achievements::record(state.unlocked);
+        }
 
         // Render
-        renderer.render(&state.borrow());
+        let mut debug = debug.borrow_mut();
+        debug.record_frame(dt);
+        renderer.render(&state.borrow(), &options, &debug, &summary.borrow());
 
         // Continue loop
         request_animation_frame(f.borrow().as_ref().unwrap());
@@ -64,12 +100,79 @@ fn request_animation_frame(f: &Closure<dyn FnMut(f64)>) {
         .expect("Failed to register animation frame");
 }
 
+/// Body of the file at `path`, relative to the page
+async fn fetch_text(path: &str) -> Result<String, JsValue> {
+    let response: web_sys::Response = wasm_bindgen_futures::JsFuture::from(window().unwrap().fetch_with_str(path))
+        .await?
+        .dyn_into()?;
+    if !response.ok() {
+        return Err(JsValue::from_str(&format!("HTTP {}", response.status())));
+    }
+    wasm_bindgen_futures::JsFuture::from(response.text()?)
+        .await?
+        .as_string()
+        .ok_or_else(|| JsValue::from_str("response is not text"))
+}
+
+/// Start a game with the balance config from the URL: `?config=<file>`
+/// is fetched (the game waits on the loading screen for it), dotted keys
+/// like `?projectile.speed=12` apply on top. `pending` clears once the
+/// state holds the final config.
+fn load_config(state: Rc<RefCell<GameState>>, pending: Rc<Cell<bool>>) {
+    let search = window().unwrap().location().search().unwrap_or_default();
+    let Some(path) = config::query_param(&search, "config") else {
+        *state.borrow_mut() = GameState::new(GameConfig::load(None, &search));
+        return;
+    };
+    pending.set(true);
+    wasm_bindgen_futures::spawn_local(async move {
+        let file = match fetch_text(&path).await {
+            Ok(text) => Some(text),
+            Err(err) => {
+                log::warn!("Config file {} not loaded: {:?}", path, err);
+                None
+            }
+        };
+        *state.borrow_mut() = GameState::new(GameConfig::load(file.as_deref(), &search));
+        pending.set(false);
+    });
+}
+
+/// `?renderer=webgl2` opts into the WebGL2 backend; anything else, or a
+/// browser without WebGL2, gets Canvas2D. A canvas can only ever hand out
+/// one kind of context, so the choice has to be made before either is created.
+fn create_backend(canvas: &HtmlCanvasElement, (width, height): (f64, f64)) -> Result<Box<dyn RenderBackend>, JsValue> {
+    let search = window().unwrap().location().search().unwrap_or_default();
+    let wants_webgl = search
+        .trim_start_matches('?')
+        .split('&')
+        .any(|pair| pair == "renderer=webgl2");
+
+    if wants_webgl {
+        match WebGl2Backend::new(canvas, width, height) {
+            Ok(backend) => {
+                log::info!("Renderer: WebGL2");
+                return Ok(Box::new(backend));
+            }
+            Err(err) => log::warn!("WebGL2 unavailable ({:?}), falling back to Canvas2D", err),
+        }
+    }
+
+    let ctx = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    log::info!("Renderer: Canvas2D");
+    Ok(Box::new(Canvas2dBackend::new(ctx, width, height)))
+}
+
 #[wasm_bindgen(start)]
 pub fn main() -> Result<(), JsValue> {
-    console_log!("RENEGADE starting with working audio...");
+    logging::init();
+    log::info!("RENEGADE starting");
 
-    // Test audio on startup
-    audio::play(Sound::Shoot);
+    // Fetch and decode samples while the loading screen is up
+    audio::preload();
 
     // Get canvas and document
     let document = window().unwrap().document().unwrap();
@@ -78,13 +181,9 @@ pub fn main() -> Result<(), JsValue> {
         .unwrap()
         .dyn_into::<HtmlCanvasElement>()?;
 
-    canvas.set_width(CANVAS_WIDTH as u32);
-    canvas.set_height(CANVAS_HEIGHT as u32);
-
-    let ctx = canvas
-        .get_context("2d")?
-        .unwrap()
-        .dyn_into::<CanvasRenderingContext2d>()?;
+    // Backing store follows the window and devicePixelRatio; the world stays WORLD_WIDTH x WORLD_HEIGHT
+    let size = display::fit_canvas(&canvas)?;
+    let backend = create_backend(&canvas, size)?;
 
     // Hide loading message
     if let Some(loading) = document.get_element_by_id("loading") {
@@ -93,59 +192,33 @@ pub fn main() -> Result<(), JsValue> {
 
     // Start background music on first user interaction
     let start_music = Closure::wrap(Box::new(move |_event: web_sys::Event| {
-        console_log!("Starting background music...");
+        log::debug!("Starting background music");
         audio::looped(Sound::Background);
     }) as Box<dyn FnMut(_)>);
     
     document.add_event_listener_with_callback("click", start_music.as_ref().unchecked_ref())?;
     start_music.forget();
     
-    // Add global restart function for background music looping
-    let restart_music_func = Closure::wrap(Box::new(|| {
-        audio::looped(Sound::Background);
-    }) as Box<dyn Fn()>);
-    
-    js_sys::Reflect::set(
-        &web_sys::window().unwrap(),
-        &"restartBackgroundMusic".into(),
-        restart_music_func.as_ref()
-    )?;
-    restart_music_func.forget();
-
     // Initialize game systems
-    let renderer = Rc::new(Renderer::new(ctx));
-    let state = Rc::new(RefCell::new(GameState::new()));
+    let renderer = Renderer::new(backend);
+    let shared = Shared::default();
+    load_config(shared.state.clone(), shared.config_pending.clone());
+
+    // Set up controls
+    let input = Rc::new(InputHandler::new(
+        shared.state.clone(),
+        shared.options.clone(),
+        shared.debug.clone(),
+        shared.summary.clone(),
+    ));
 
-    // Set up controls - SIMPLIFIED
-    let state_for_keydown = state.clone();
+    let input_for_keydown = input.clone();
     let keydown = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
-        // Initialize audio on first key press
-        audio::init_audio_context();
-        
-        let mut game_state = state_for_keydown.borrow_mut();
-        match event.key().as_str() {
-            "ArrowLeft" | "a" | "A" => {
-                game_state.player.speed = -0.05; // Faster for visibility
-            }
-            "ArrowRight" | "d" | "D" => {
-                game_state.player.speed = 0.05; // Faster for visibility
-            }
-            " " => {
-                game_state.shoot();
-            }
-            _ => {}
-        }
+        input_for_keydown.handle_keydown(event);
     }) as Box<dyn FnMut(_)>);
 
-    let state_for_keyup = state.clone();
     let keyup = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
-        let mut game_state = state_for_keyup.borrow_mut();
-        match event.key().as_str() {
-            "ArrowLeft" | "a" | "A" | "ArrowRight" | "d" | "D" => {
-                game_state.player.speed = 0.02; // Default orbital speed
-            }
-            _ => {}
-        }
+        input.handle_keyup(event);
     }) as Box<dyn FnMut(_)>);
 
     document.add_event_listener_with_callback("keydown", keydown.as_ref().unchecked_ref())?;
@@ -154,10 +227,30 @@ pub fn main() -> Result<(), JsValue> {
     keydown.forget();
     keyup.forget();
 
+    // Go quiet while the tab is in the background
+    let document_for_visibility = document.clone();
+    let visibility_change = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+        audio::set_tab_hidden(document_for_visibility.hidden());
+    }) as Box<dyn FnMut(_)>);
+
+    document.add_event_listener_with_callback("visibilitychange", visibility_change.as_ref().unchecked_ref())?;
+    visibility_change.forget();
+
+    // Window resizes, zoom (devicePixelRatio) changes and fullscreen all
+    // land here; the game loop refits the canvas on its next frame
+    let resized_flag = shared.resized.clone();
+    let on_resize = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+        resized_flag.set(true);
+    }) as Box<dyn FnMut(_)>);
+
+    let window = window().unwrap();
+    window.add_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref())?;
+    document.add_event_listener_with_callback("fullscreenchange", on_resize.as_ref().unchecked_ref())?;
+    on_resize.forget();
+
     // Start game loop
-    let last_time = Rc::new(RefCell::new(0.0));
-    game_loop(renderer, state, last_time);
+    game_loop(renderer, canvas, shared);
 
-    console_log!("RENEGADE initialization complete!");
+    log::info!("RENEGADE initialized");
     Ok(())
 }
\ No newline at end of file
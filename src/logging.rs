@@ -0,0 +1,161 @@
+//! `log` backend: the browser console on the web, stderr everywhere else
+//!
+//! Levels are set per module with a filter spec in the `env_logger` style,
+//! `warn,renegade::audio=debug,renegade::game::systems=trace`: a bare level
+//! is the default, `module=level` applies to that module and everything
+//! under it, and the longest matching module wins. The spec comes from
+//! `?log=` on the page URL, then the `renegade.log` localStorage key, then
+//! `RUST_LOG` off the web, and can be swapped at runtime with
+//! `set_filter` (exported to JS as `set_log_filter`).
+//!
+//! Release builds compile `debug!` and `trace!` out entirely (the `log`
+//! crate's `release_max_level_info` feature), whatever the filter says.
+
+use std::sync::RwLock;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use wasm_bindgen::prelude::*;
+use crate::storage;
+
+const STORAGE_KEY: &str = "log";
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+static LOGGER: Logger = Logger { filter: RwLock::new(Filter { default: DEFAULT_LEVEL, modules: Vec::new() }) };
+
+#[derive(Debug, PartialEq)]
+struct Filter {
+    default: LevelFilter,
+    /// (module path, level), longest path first
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    /// Parse a spec, skipping (and reporting) entries that don't parse
+    fn parse(spec: &str) -> (Self, Vec<String>) {
+        let mut filter = Filter { default: DEFAULT_LEVEL, modules: Vec::new() };
+        let mut rejected = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((module, level)) => match level.trim().parse() {
+                    Ok(level) => filter.modules.push((module.trim().to_string(), level)),
+                    Err(_) => rejected.push(entry.to_string()),
+                },
+                None => match entry.parse() {
+                    Ok(level) => filter.default = level,
+                    Err(_) => rejected.push(entry.to_string()),
+                },
+            }
+        }
+        filter.modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        (filter, rejected)
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .find(|(module, _)| {
+                target.strip_prefix(module.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    /// Most verbose level anything is allowed, for `log::set_max_level`
+    fn max_level(&self) -> LevelFilter {
+        self.modules.iter().map(|&(_, level)| level).fold(self.default, Ord::max)
+    }
+}
+
+struct Logger {
+    filter: RwLock<Filter>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.read().is_ok_and(|filter| metadata.level() <= filter.level_for(metadata.target()))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        write(record.level(), &format!("[{} {}] {}", record.level(), record.target(), record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(level: Level, line: &str) {
+    let line = JsValue::from_str(line);
+    match level {
+        Level::Error => web_sys::console::error_1(&line),
+        Level::Warn => web_sys::console::warn_1(&line),
+        Level::Info => web_sys::console::info_1(&line),
+        Level::Debug | Level::Trace => web_sys::console::debug_1(&line),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(_level: Level, line: &str) {
+    eprintln!("{line}");
+}
+
+/// Install the logger with the filter from the URL, storage or environment.
+/// Safe to call more than once; only the first call installs.
+pub fn init() {
+    if log::set_logger(&LOGGER).is_err() {
+        return;
+    }
+    apply(&initial_spec().unwrap_or_default());
+}
+
+fn initial_spec() -> Option<String> {
+    if cfg!(target_arch = "wasm32") {
+        let search = web_sys::window()?.location().search().ok()?;
+        crate::game::config::query_param(&search, "log").or_else(|| storage::load(STORAGE_KEY))
+    } else {
+        std::env::var("RUST_LOG").ok()
+    }
+}
+
+/// Replace the filter, e.g. `set_log_filter("debug")` from the browser
+/// console; saved so it survives a reload
+#[wasm_bindgen(js_name = set_log_filter)]
+pub fn set_filter(spec: &str) {
+    apply(spec);
+    storage::save(STORAGE_KEY, spec);
+}
+
+fn apply(spec: &str) {
+    let (filter, rejected) = Filter::parse(spec);
+    log::set_max_level(filter.max_level());
+    if let Ok(mut current) = LOGGER.filter.write() {
+        *current = filter;
+    }
+    for entry in rejected {
+        log::warn!("Ignoring log filter entry {entry:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_module_wins() {
+        let (filter, rejected) = Filter::parse("warn, renegade::audio=debug, renegade::audio::music=off, bogus=loud");
+        assert_eq!(rejected, ["bogus=loud"]);
+        assert_eq!(filter.level_for("renegade::game::systems"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("renegade::audio"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("renegade::audio::buffers"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("renegade::audio::music"), LevelFilter::Off);
+        assert_eq!(filter.level_for("renegade::audiobook"), LevelFilter::Warn, "prefix must end at a module boundary");
+        assert_eq!(filter.max_level(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn empty_spec_is_the_default() {
+        let (filter, rejected) = Filter::parse("");
+        assert!(rejected.is_empty());
+        assert_eq!(filter, Filter { default: DEFAULT_LEVEL, modules: Vec::new() });
+    }
+}
@@ -0,0 +1,181 @@
+//! Drawing primitives shared by every render backend
+//!
+//! `Renderer` only ever talks to a `RenderBackend`; the backends decide how
+//! a filled circle or a line of text actually reaches the screen.
+
+use crate::rendering::crt::CrtParams;
+use crate::rendering::glow::GlowParams;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// 0..1
+    pub a: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: f32) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// `0xRRGGBB`
+    pub const fn hex(rgb: u32) -> Self {
+        Color::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    pub fn with_alpha(self, a: f32) -> Self {
+        Color { a, ..self }
+    }
+
+    /// Components as 0..1 floats, alpha last
+    pub fn to_f32(self) -> [f32; 4] {
+        [self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0, self.a]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Regular alpha blending
+    Normal,
+    /// Colours add up; overlapping glows get brighter
+    Additive,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// 2D affine transform: `x' = a*x + c*y + e`, `y' = b*x + d*y + f`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 };
+
+    /// `self` followed by `other` applied in local space (canvas semantics)
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    pub fn translation(x: f64, y: f64) -> Transform {
+        Transform { e: x, f: y, ..Transform::IDENTITY }
+    }
+
+    pub fn rotation(angle: f64) -> Transform {
+        let (sin, cos) = angle.sin_cos();
+        Transform { a: cos, b: sin, c: -sin, d: cos, e: 0.0, f: 0.0 }
+    }
+
+    pub fn scaling(sx: f64, sy: f64) -> Transform {
+        Transform { a: sx, d: sy, ..Transform::IDENTITY }
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.c * y + self.e, self.b * x + self.d * y + self.f)
+    }
+
+    /// Average linear scale, for sizing radii and line widths
+    pub fn scale_factor(&self) -> f64 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+}
+
+/// Save/restore stack for backends that transform on the CPU
+#[derive(Clone, Debug)]
+pub struct TransformStack {
+    pub current: Transform,
+    saved: Vec<Transform>,
+}
+
+impl Default for TransformStack {
+    fn default() -> Self {
+        TransformStack { current: Transform::IDENTITY, saved: Vec::new() }
+    }
+}
+
+impl TransformStack {
+    pub fn push(&mut self) {
+        self.saved.push(self.current);
+    }
+
+    pub fn pop(&mut self) {
+        if let Some(t) = self.saved.pop() {
+            self.current = t;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.current = Transform::IDENTITY;
+        self.saved.clear();
+    }
+
+    pub fn apply(&mut self, t: &Transform) {
+        self.current = self.current.then(t);
+    }
+}
+
+pub trait RenderBackend {
+    /// Called before anything is drawn for a frame; resets transform and blend state
+    fn begin_frame(&mut self);
+    /// Called after the last draw of a frame; batching backends flush here
+    fn end_frame(&mut self);
+
+    /// Backing-store size in pixels
+    fn size(&self) -> (f64, f64);
+    /// The backing store was resized to `width` x `height` pixels
+    fn resize(&mut self, width: f64, height: f64);
+
+    /// Fill the whole target, ignoring the current transform
+    fn clear(&mut self, color: Color);
+
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Color);
+    fn fill_circle(&mut self, x: f64, y: f64, radius: f64, color: Color);
+    fn stroke_circle(&mut self, x: f64, y: f64, radius: f64, width: f64, color: Color);
+    /// Convex or simple polygon, closed automatically
+    fn fill_polygon(&mut self, points: &[(f64, f64)], color: Color);
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, width: f64, color: Color);
+    /// `y` is the text baseline; `size` is the font size in pixels
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, align: TextAlign, color: Color);
+
+    fn set_blend_mode(&mut self, mode: BlendMode);
+
+    /// Send following draws to a cleared offscreen glow layer instead of the scene
+    fn begin_glow(&mut self, params: GlowParams);
+    /// Blur the glow layer and add it over the scene; draws go to the scene again
+    fn end_glow(&mut self);
+
+    /// Run the CRT pass over everything drawn so far this frame
+    fn apply_crt(&mut self, params: CrtParams);
+
+    fn push_transform(&mut self);
+    fn pop_transform(&mut self);
+    fn translate(&mut self, x: f64, y: f64);
+    fn rotate(&mut self, angle: f64);
+    fn scale(&mut self, sx: f64, sy: f64);
+}
@@ -0,0 +1,163 @@
+//! View onto the world: position, zoom and rotation, plus impact effects
+//!
+//! The camera is rebuilt from the game state every frame rather than
+//! simulated, so it pauses with the game and stays deterministic for the
+//! screenshot tests. Shake follows the usual trauma model: `screen_shake`
+//! is trauma, its square drives the offset, and the motion comes from
+//! smooth 1D Perlin noise sampled at the game clock, so the view rolls
+//! instead of teleporting to a new random spot every frame.
+
+use std::f64::consts::FRAC_PI_2;
+use crate::constants::*;
+use crate::game::GameState;
+use crate::rendering::backend::RenderBackend;
+use crate::settings::Choice;
+
+/// Offset and roll at full trauma, in world units and radians
+const MAX_SHAKE_OFFSET: f64 = 20.0;
+const MAX_SHAKE_ROLL: f64 = 0.05;
+/// Noise samples per second; higher is more violent
+const SHAKE_FREQUENCY: f64 = 18.0;
+/// Extra zoom at the peak of a punch
+const PUNCH_ZOOM: f64 = 0.18;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    /// World upright, planet centred
+    #[default]
+    Fixed,
+    /// View turns with the ship so it always sits at the bottom of the screen
+    Follow,
+}
+
+impl Choice for CameraMode {
+    const ALL: &'static [Self] = &[CameraMode::Fixed, CameraMode::Follow];
+
+    fn label(self) -> &'static str {
+        match self {
+            CameraMode::Fixed => "FIXED",
+            CameraMode::Follow => "FOLLOW",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// World point at the centre of the view
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+    /// Radians, applied around the view centre
+    pub rotation: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera { x: WORLD_WIDTH / 2.0, y: WORLD_HEIGHT / 2.0, zoom: 1.0, rotation: 0.0 }
+    }
+}
+
+impl Camera {
+    /// Where the camera is for `state` this frame
+    pub fn for_state(state: &GameState, mode: CameraMode) -> Self {
+        let mut camera = Camera::default();
+
+        if mode == CameraMode::Follow {
+            // Turn the ship's orbit angle to straight down
+            camera.rotation = FRAC_PI_2 - state.player.angle;
+        }
+
+        // Ease out of the punch so it snaps in and drifts back
+        let punch = state.zoom_punch.clamp(0.0, 1.0);
+        camera.zoom += PUNCH_ZOOM * punch * punch;
+
+        let trauma = (state.screen_shake / state.config.shake.max).clamp(0.0, 1.0);
+        let shake = trauma * trauma;
+        if shake > 0.0 {
+            let t = state.time * SHAKE_FREQUENCY;
+            camera.x += MAX_SHAKE_OFFSET * shake * perlin(t, 0);
+            camera.y += MAX_SHAKE_OFFSET * shake * perlin(t, 1);
+            camera.rotation += MAX_SHAKE_ROLL * shake * perlin(t, 2);
+        }
+        camera
+    }
+
+    /// Make the world appear through this camera in the current transform
+    pub fn apply(&self, backend: &mut dyn RenderBackend) {
+        backend.translate(WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        backend.rotate(self.rotation);
+        backend.scale(self.zoom, self.zoom);
+        backend.translate(-self.x, -self.y);
+    }
+}
+
+/// Gradient for lattice point `i` of noise channel `channel`, in -1..1
+fn gradient(i: i64, channel: u64) -> f64 {
+    let mut h = (i as u64) ^ channel.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    h = (h ^ (h >> 33)).wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h = (h ^ (h >> 33)).wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    h ^= h >> 33;
+    (h >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+}
+
+/// 1D Perlin noise, roughly -1..1, zero at every integer
+fn perlin(t: f64, channel: u64) -> f64 {
+    let i = t.floor();
+    let f = t - i;
+    let (g0, g1) = (gradient(i as i64, channel), gradient(i as i64 + 1, channel));
+    let fade = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    // Each gradient contributes at most 0.5, so double to fill the range
+    2.0 * (g0 * f + (g1 * (f - 1.0) - g0 * f) * fade)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follow_mode_keeps_the_ship_at_the_bottom() {
+        let mut state = GameState::with_seed(1);
+        for angle in [0.0, 1.0, FRAC_PI_2, 3.5, 6.0] {
+            state.player.angle = angle;
+            let camera = Camera::for_state(&state, CameraMode::Follow);
+            let (x, y) = state.player.get_position();
+            let (dx, dy) = (x - camera.x, y - camera.y);
+            let (sin, cos) = camera.rotation.sin_cos();
+            // Where the ship lands relative to the view centre, y down
+            let (sx, sy) = (dx * cos - dy * sin, dx * sin + dy * cos);
+            assert!(sx.abs() < 1e-9, "angle {angle}: {sx} off centre");
+            assert!(sy > 0.0, "angle {angle}: above the planet");
+        }
+        assert_eq!(Camera::for_state(&state, CameraMode::Fixed).rotation, 0.0);
+    }
+
+    #[test]
+    fn shake_stays_within_its_bounds() {
+        let mut state = GameState::with_seed(1);
+        assert_eq!(Camera::for_state(&state, CameraMode::Fixed), Camera::default(), "no trauma, no shake");
+
+        // Trauma past the maximum doesn't shake any harder
+        state.screen_shake = state.config.shake.max * 2.0;
+        let (mut moved, center) = (false, Camera::default());
+        for frame in 0..600 {
+            state.time = frame as f64 / 60.0;
+            let camera = Camera::for_state(&state, CameraMode::Fixed);
+            assert!((camera.x - center.x).abs() <= MAX_SHAKE_OFFSET);
+            assert!((camera.y - center.y).abs() <= MAX_SHAKE_OFFSET);
+            assert!(camera.rotation.abs() <= MAX_SHAKE_ROLL);
+            moved |= camera.x != center.x;
+        }
+        assert!(moved);
+    }
+
+    #[test]
+    fn perlin_is_bounded_and_zero_on_the_lattice() {
+        for i in -50..50 {
+            assert_eq!(perlin(i as f64, 0), 0.0);
+            for step in 1..10 {
+                let value = perlin(i as f64 + step as f64 / 10.0, 1);
+                assert!((-1.0..=1.0).contains(&value), "{value}");
+            }
+        }
+    }
+}
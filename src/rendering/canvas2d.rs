@@ -0,0 +1,399 @@
+//! `RenderBackend` over `CanvasRenderingContext2d`
+//!
+//! Fill colours are set as an `rgb` string plus `globalAlpha`, and both are
+//! only touched when they change, so runs of same-coloured shapes (a burst
+//! of particles) don't format a CSS string per shape.
+//!
+//! The glow layer is a second, smaller canvas. It is composited with the
+//! `lighter` operation through a CSS `blur()` filter, which is already a
+//! true gaussian, so `GlowParams::passes` is not needed here.
+//!
+//! Reading the frame back for `crt::apply` every frame is far too slow, so
+//! the CRT pass is built from image copies the browser does on the GPU. The
+//! curvature copies the frame back onto itself in `crt::warp_tiles`, each
+//! tile stretched a little outwards. The aberration splits the channels
+//! with `multiply` fills and adds them back with `lighter`, red and blue
+//! scaled about the centre. Scanlines and vignette come from a
+//! `crt::overlay` image rendered once per size and setting, and flicker is
+//! a translucent black fill.
+
+use std::f64::consts::TAU;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+use crate::rendering::backend::{BlendMode, Color, RenderBackend, TextAlign};
+use crate::rendering::crt::{self, CrtParams};
+use crate::rendering::glow::GlowParams;
+
+/// Grid the curvature is approximated on; finer bends more smoothly but
+/// costs a copy per tile
+const WARP_COLS: usize = 24;
+const WARP_ROWS: usize = 18;
+
+pub struct Canvas2dBackend {
+    /// Where draws currently go: the scene, or the glow layer
+    ctx: CanvasRenderingContext2d,
+    scene: CanvasRenderingContext2d,
+    glow_layer: Option<(HtmlCanvasElement, CanvasRenderingContext2d)>,
+    glow: Option<GlowParams>,
+    /// Pre-rendered CRT darkening and the settings it was drawn for
+    crt_overlay: Option<(HtmlCanvasElement, CrtParams)>,
+    /// Scratch canvases for the CRT warp and channel split: a copy of the
+    /// frame, and one channel of it
+    crt_layers: Option<[(HtmlCanvasElement, CanvasRenderingContext2d); 2]>,
+    width: f64,
+    height: f64,
+    fill_rgb: Option<(u8, u8, u8)>,
+    stroke_rgb: Option<(u8, u8, u8)>,
+    alpha: f32,
+    font_size: f64,
+}
+
+impl Canvas2dBackend {
+    pub fn new(ctx: CanvasRenderingContext2d, width: f64, height: f64) -> Self {
+        Canvas2dBackend {
+            scene: ctx.clone(),
+            ctx,
+            glow_layer: None,
+            glow: None,
+            crt_overlay: None,
+            crt_layers: None,
+            width,
+            height,
+            fill_rgb: None,
+            stroke_rgb: None,
+            alpha: 1.0,
+            font_size: 0.0,
+        }
+    }
+
+    fn set_alpha(&mut self, a: f32) {
+        if a != self.alpha {
+            self.ctx.set_global_alpha(a as f64);
+            self.alpha = a;
+        }
+    }
+
+    fn set_fill(&mut self, color: Color) {
+        let rgb = (color.r, color.g, color.b);
+        if self.fill_rgb != Some(rgb) {
+            self.ctx.set_fill_style_str(&css_rgb(rgb));
+            self.fill_rgb = Some(rgb);
+        }
+        self.set_alpha(color.a);
+    }
+
+    fn set_stroke(&mut self, color: Color, width: f64) {
+        let rgb = (color.r, color.g, color.b);
+        if self.stroke_rgb != Some(rgb) {
+            self.ctx.set_stroke_style_str(&css_rgb(rgb));
+            self.stroke_rgb = Some(rgb);
+        }
+        self.ctx.set_line_width(width);
+        self.set_alpha(color.a);
+    }
+
+    /// The offscreen glow canvas, (re)created at the size `params` asks for
+    fn glow_layer(&mut self, params: GlowParams) -> Option<(HtmlCanvasElement, CanvasRenderingContext2d)> {
+        let width = (self.width / params.downscale).ceil() as u32;
+        let height = (self.height / params.downscale).ceil() as u32;
+        if self.glow_layer.is_none() {
+            let canvas: HtmlCanvasElement = web_sys::window()?
+                .document()?
+                .create_element("canvas")
+                .ok()?
+                .dyn_into()
+                .ok()?;
+            let ctx: CanvasRenderingContext2d = canvas.get_context("2d").ok()??.dyn_into().ok()?;
+            self.glow_layer = Some((canvas, ctx));
+        }
+        let (canvas, ctx) = self.glow_layer.as_ref()?;
+        if canvas.width() != width || canvas.height() != height {
+            canvas.set_width(width);
+            canvas.set_height(height);
+        }
+        Some((canvas.clone(), ctx.clone()))
+    }
+
+    /// The CRT overlay for `params` at the current size, redrawn only when
+    /// either changes
+    fn crt_overlay(&mut self, params: CrtParams) -> Option<HtmlCanvasElement> {
+        // Only the darkening is baked in
+        let key = CrtParams { curvature: 0.0, aberration: 0.0, brightness: 1.0, ..params };
+        let (width, height) = (self.width as u32, self.height as u32);
+        if let Some((canvas, drawn)) = &self.crt_overlay {
+            if *drawn == key && canvas.width() == width && canvas.height() == height {
+                return Some(canvas.clone());
+            }
+        }
+
+        let canvas: HtmlCanvasElement = match self.crt_overlay.take() {
+            Some((canvas, _)) => canvas,
+            None => web_sys::window()?.document()?.create_element("canvas").ok()?.dyn_into().ok()?,
+        };
+        canvas.set_width(width);
+        canvas.set_height(height);
+        let ctx: CanvasRenderingContext2d = canvas.get_context("2d").ok()??.dyn_into().ok()?;
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        crt::overlay(&mut pixels, width as usize, height as usize, &key);
+        let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), width, height).ok()?;
+        ctx.put_image_data(&image, 0.0, 0.0).ok()?;
+        self.crt_overlay = Some((canvas.clone(), key));
+        Some(canvas)
+    }
+
+    /// The CRT scratch canvases at the current size
+    fn crt_layers(&mut self) -> Option<[(HtmlCanvasElement, CanvasRenderingContext2d); 2]> {
+        if self.crt_layers.is_none() {
+            let document = web_sys::window()?.document()?;
+            let layer = || -> Option<(HtmlCanvasElement, CanvasRenderingContext2d)> {
+                let canvas: HtmlCanvasElement = document.create_element("canvas").ok()?.dyn_into().ok()?;
+                let ctx = canvas.get_context("2d").ok()??.dyn_into().ok()?;
+                Some((canvas, ctx))
+            };
+            self.crt_layers = Some([layer()?, layer()?]);
+        }
+        let layers = self.crt_layers.clone()?;
+        let (width, height) = (self.width as u32, self.height as u32);
+        for (canvas, _) in &layers {
+            if canvas.width() != width || canvas.height() != height {
+                canvas.set_width(width);
+                canvas.set_height(height);
+            }
+        }
+        Some(layers)
+    }
+
+    /// Copy the whole scene into `ctx`
+    fn copy_scene(&self, ctx: &CanvasRenderingContext2d) {
+        let _ = ctx.set_global_composite_operation("copy");
+        if let Some(scene) = self.scene.canvas() {
+            let _ = ctx.draw_image_with_html_canvas_element(&scene, 0.0, 0.0);
+        }
+    }
+
+    /// Bend the scene outwards, copying it back onto itself tile by tile
+    fn crt_warp(&self, frame: &(HtmlCanvasElement, CanvasRenderingContext2d), curvature: f32) {
+        let (canvas, ctx) = frame;
+        self.copy_scene(ctx);
+        self.scene.set_fill_style_str("#000000");
+        self.scene.fill_rect(0.0, 0.0, self.width, self.height);
+        for ([sx, sy, sw, sh], [dx, dy, dw, dh]) in crt::warp_tiles(self.width, self.height, WARP_COLS, WARP_ROWS, curvature) {
+            let _ = self.scene
+                .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(canvas, sx, sy, sw, sh, dx, dy, dw, dh);
+        }
+    }
+
+    /// Split the scene's red and blue from its green, scaled about the centre
+    fn crt_aberration(&self, layers: &[(HtmlCanvasElement, CanvasRenderingContext2d); 2], params: &CrtParams) {
+        let [(frame, frame_ctx), (channel, channel_ctx)] = layers;
+        self.copy_scene(frame_ctx);
+        let (red, blue) = crt::aberration_scales(self.width as usize, params);
+        let keep = |ctx: &CanvasRenderingContext2d, color: &str| {
+            let _ = ctx.set_global_composite_operation("multiply");
+            ctx.set_fill_style_str(color);
+            ctx.fill_rect(0.0, 0.0, self.width, self.height);
+        };
+        keep(&self.scene, "#00ff00");
+        for (scale, color) in [(red, "#ff0000"), (blue, "#0000ff")] {
+            let _ = channel_ctx.set_global_composite_operation("source-over");
+            channel_ctx.set_fill_style_str("#000000");
+            channel_ctx.fill_rect(0.0, 0.0, self.width, self.height);
+            let (w, h) = (self.width * scale as f64, self.height * scale as f64);
+            let _ = channel_ctx
+                .draw_image_with_html_canvas_element_and_dw_and_dh(frame, (self.width - w) / 2.0, (self.height - h) / 2.0, w, h);
+            keep(channel_ctx, color);
+            let _ = self.scene.set_global_composite_operation("lighter");
+            let _ = self.scene.draw_image_with_html_canvas_element(channel, 0.0, 0.0);
+        }
+    }
+
+    /// Forget cached state after anything that may have reset the context
+    fn invalidate(&mut self) {
+        self.fill_rgb = None;
+        self.stroke_rgb = None;
+        self.alpha = self.ctx.global_alpha() as f32;
+        self.font_size = 0.0;
+    }
+}
+
+fn css_rgb((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+impl RenderBackend for Canvas2dBackend {
+    fn begin_frame(&mut self) {
+        self.ctx = self.scene.clone();
+        self.glow = None;
+        let _ = self.ctx.reset_transform();
+        let _ = self.ctx.set_global_composite_operation("source-over");
+        self.invalidate();
+    }
+
+    fn end_frame(&mut self) {}
+
+    fn size(&self) -> (f64, f64) {
+        (self.width, self.height)
+    }
+
+    fn resize(&mut self, width: f64, height: f64) {
+        // Resizing the canvas resets its context; begin_frame re-syncs the caches
+        self.width = width;
+        self.height = height;
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.ctx.save();
+        let _ = self.ctx.reset_transform();
+        self.set_fill(color);
+        self.ctx.fill_rect(0.0, 0.0, self.width, self.height);
+        self.ctx.restore();
+        self.invalidate();
+    }
+
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Color) {
+        self.set_fill(color);
+        self.ctx.fill_rect(x, y, w, h);
+    }
+
+    fn fill_circle(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+        self.set_fill(color);
+        self.ctx.begin_path();
+        let _ = self.ctx.arc(x, y, radius.max(0.0), 0.0, TAU);
+        self.ctx.fill();
+    }
+
+    fn stroke_circle(&mut self, x: f64, y: f64, radius: f64, width: f64, color: Color) {
+        self.set_stroke(color, width);
+        self.ctx.begin_path();
+        let _ = self.ctx.arc(x, y, radius.max(0.0), 0.0, TAU);
+        self.ctx.stroke();
+    }
+
+    fn fill_polygon(&mut self, points: &[(f64, f64)], color: Color) {
+        let Some(&(x0, y0)) = points.first() else { return };
+        self.set_fill(color);
+        self.ctx.begin_path();
+        self.ctx.move_to(x0, y0);
+        for &(x, y) in &points[1..] {
+            self.ctx.line_to(x, y);
+        }
+        self.ctx.close_path();
+        self.ctx.fill();
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, width: f64, color: Color) {
+        self.set_stroke(color, width);
+        self.ctx.begin_path();
+        self.ctx.move_to(x1, y1);
+        self.ctx.line_to(x2, y2);
+        self.ctx.stroke();
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, align: TextAlign, color: Color) {
+        if size != self.font_size {
+            self.ctx.set_font(&format!("{size}px Arial"));
+            self.font_size = size;
+        }
+        self.ctx.set_text_align(match align {
+            TextAlign::Left => "left",
+            TextAlign::Center => "center",
+            TextAlign::Right => "right",
+        });
+        self.set_fill(color);
+        let _ = self.ctx.fill_text(text, x, y);
+    }
+
+    fn set_blend_mode(&mut self, mode: BlendMode) {
+        let op = match mode {
+            BlendMode::Normal => "source-over",
+            BlendMode::Additive => "lighter",
+        };
+        let _ = self.ctx.set_global_composite_operation(op);
+    }
+
+    fn begin_glow(&mut self, params: GlowParams) {
+        let Some((_, glow)) = self.glow_layer(params) else { return };
+        let _ = glow.reset_transform();
+        glow.clear_rect(0.0, 0.0, self.width, self.height);
+        let _ = glow.set_global_composite_operation("source-over");
+
+        // Keep drawing in screen space, shrunk onto the smaller layer
+        let shrink = 1.0 / params.downscale;
+        if let Ok(m) = self.scene.get_transform() {
+            let _ = glow.set_transform(m.a() * shrink, m.b() * shrink, m.c() * shrink, m.d() * shrink, m.e() * shrink, m.f() * shrink);
+        }
+        self.ctx = glow;
+        self.glow = Some(params);
+        self.invalidate();
+    }
+
+    fn end_glow(&mut self) {
+        let Some(params) = self.glow.take() else { return };
+        self.ctx = self.scene.clone();
+        let Some((canvas, _)) = self.glow_layer.as_ref() else { return };
+
+        self.scene.save();
+        let _ = self.scene.reset_transform();
+        let _ = self.scene.set_global_composite_operation("lighter");
+        self.scene.set_filter(&format!("blur({}px)", params.radius));
+        // globalAlpha stops at 1, so brighter settings add the layer twice
+        let mut remaining = params.intensity;
+        while remaining > 0.0 {
+            self.scene.set_global_alpha(remaining.min(1.0) as f64);
+            let _ = self.scene.draw_image_with_html_canvas_element_and_dw_and_dh(canvas, 0.0, 0.0, self.width, self.height);
+            remaining -= 1.0;
+        }
+        self.scene.restore();
+        self.invalidate();
+    }
+
+    fn apply_crt(&mut self, params: CrtParams) {
+        let Some(overlay) = self.crt_overlay(params) else { return };
+        self.scene.save();
+        let _ = self.scene.reset_transform();
+        self.scene.set_global_alpha(1.0);
+        if params.curvature != 0.0 || params.aberration != 0.0 {
+            if let Some(layers) = self.crt_layers() {
+                let _ = self.scene.set_global_composite_operation("source-over");
+                if params.curvature != 0.0 {
+                    self.crt_warp(&layers[0], params.curvature);
+                }
+                if params.aberration != 0.0 {
+                    self.crt_aberration(&layers, &params);
+                }
+            }
+        }
+        let _ = self.scene.set_global_composite_operation("source-over");
+        let _ = self.scene.draw_image_with_html_canvas_element(&overlay, 0.0, 0.0);
+        let flicker = 1.0 - params.brightness;
+        if flicker > 0.0 {
+            self.scene.set_global_alpha(flicker as f64);
+            self.scene.set_fill_style_str("#000000");
+            self.scene.fill_rect(0.0, 0.0, self.width, self.height);
+        }
+        self.scene.restore();
+        self.invalidate();
+    }
+
+    fn push_transform(&mut self) {
+        self.ctx.save();
+    }
+
+    fn pop_transform(&mut self) {
+        self.ctx.restore();
+        // restore() also rolls back fill/stroke/alpha
+        self.invalidate();
+    }
+
+    fn translate(&mut self, x: f64, y: f64) {
+        let _ = self.ctx.translate(x, y);
+    }
+
+    fn rotate(&mut self, angle: f64) {
+        let _ = self.ctx.rotate(angle);
+    }
+
+    fn scale(&mut self, sx: f64, sy: f64) {
+        let _ = self.ctx.scale(sx, sy);
+    }
+}
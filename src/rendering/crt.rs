@@ -0,0 +1,188 @@
+//! CRT monitor look applied to the finished frame
+//!
+//! Barrel curvature, chromatic aberration, scanlines, vignette and a
+//! slight brightness flicker. `apply` is the reference implementation used
+//! by the software backend; the WebGL backend runs the same maths in a
+//! fragment shader, so the two look alike. Canvas2D can't afford a per-pixel
+//! pass every frame, so it approximates: the curvature as image copies from
+//! `warp_tiles`, the aberration as red and blue copies scaled by
+//! `aberration_scales`, and the darkening as a pre-rendered `overlay`.
+
+/// Strength of each part of the effect; zero turns that part off
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrtParams {
+    /// Barrel distortion; 0.05 is a gentle bulge
+    pub curvature: f32,
+    /// Red/blue split at the screen edge, in pixels
+    pub aberration: f32,
+    /// How much every other line is darkened, 0..1
+    pub scanlines: f32,
+    /// Height of one scanline in pixels, so lines keep their size on HiDPI screens
+    pub line_height: f32,
+    /// Darkening towards the corners, 0..1
+    pub vignette: f32,
+    /// Overall brightness this frame; the renderer jitters it for flicker
+    pub brightness: f32,
+}
+
+impl Default for CrtParams {
+    fn default() -> Self {
+        CrtParams { curvature: 0.06, aberration: 1.5, scanlines: 0.18, line_height: 1.0, vignette: 0.35, brightness: 1.0 }
+    }
+}
+
+/// How far brightness dips on a flickering frame
+pub const FLICKER: f32 = 0.04;
+
+/// Centred coordinates of pixel (`x`, `y`) in -1..1, bent outwards by the curvature
+fn distort(x: usize, y: usize, width: usize, height: usize, curvature: f32) -> (f32, f32, f32) {
+    let cx = (x as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+    let cy = (y as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+    bend(cx, cy, curvature)
+}
+
+/// Centred coordinates pushed outwards by the curvature, plus how far out
+/// they started (squared)
+fn bend(cx: f32, cy: f32, curvature: f32) -> (f32, f32, f32) {
+    let r2 = cx * cx + cy * cy;
+    let bend = 1.0 + curvature * r2;
+    (cx * bend, cy * bend, r2)
+}
+
+/// Scanline and vignette gain at centred coordinates (`py`, `r2` from `distort`)
+fn darkening(py: f32, r2: f32, height: usize, params: &CrtParams) -> f32 {
+    let scan_row = ((py + 1.0) * 0.5 * height as f32 / params.line_height).floor() as i32;
+    let scan = if scan_row.rem_euclid(2) == 1 { 1.0 - params.scanlines } else { 1.0 };
+    let vignette = (1.0 - params.vignette * r2 * 0.5).max(0.0);
+    scan * vignette
+}
+
+/// Write the CRT version of straight RGBA8 `src` into `dst`, both `width` x `height`
+pub fn apply(src: &[u8], dst: &mut [u8], width: usize, height: usize, params: &CrtParams) {
+    let channel = |px: f32, py: f32, c: usize| -> f32 {
+        // Nearest sample at centred coordinates; black outside the tube
+        let u = (px + 1.0) * 0.5 * width as f32;
+        let v = (py + 1.0) * 0.5 * height as f32;
+        if u < 0.0 || v < 0.0 || u >= width as f32 || v >= height as f32 {
+            return 0.0;
+        }
+        src[(v as usize * width + u as usize) * 4 + c] as f32
+    };
+
+    let aberration = params.aberration / width as f32 * 2.0;
+    for y in 0..height {
+        for x in 0..width {
+            let (px, py, r2) = distort(x, y, width, height, params.curvature);
+
+            let gain = darkening(py, r2, height, params) * params.brightness;
+
+            let rgb = [
+                channel(px + px * aberration, py, 0),
+                channel(px, py, 1),
+                channel(px - px * aberration, py, 2),
+            ];
+            let out = &mut dst[(y * width + x) * 4..][..4];
+            for (o, c) in out.iter_mut().zip(rgb) {
+                *o = (c * gain).round().clamp(0.0, 255.0) as u8;
+            }
+            out[3] = 255;
+        }
+    }
+}
+
+/// The curvature as `cols` x `rows` plain image copies: for each tile of
+/// a `width` x `height` frame, the rectangle to copy from and the one to
+/// copy it to, as (x, y, width, height) in pixels. The copies together
+/// cover the frame; parts of a source past the edge are the black outside
+/// the tube.
+pub fn warp_tiles(width: f64, height: f64, cols: usize, rows: usize, curvature: f32) -> impl Iterator<Item = ([f64; 4], [f64; 4])> {
+    let edge = |i: usize, n: usize, size: f64| (i as f64 * size / n as f64).round();
+    (0..rows).flat_map(move |row| (0..cols).map(move |col| (col, row))).map(move |(col, row)| {
+        let (x0, x1) = (edge(col, cols, width), edge(col + 1, cols, width));
+        let (y0, y1) = (edge(row, rows, height), edge(row + 1, rows, height));
+        // The bend is monotonic, so corners, edge midpoints and the centre
+        // bound where the tile samples from
+        let (mut min, mut max) = ([f32::MAX; 2], [f32::MIN; 2]);
+        for fy in [0.0, 0.5, 1.0] {
+            for fx in [0.0, 0.5, 1.0] {
+                let cx = ((x0 + (x1 - x0) * fx) / width * 2.0 - 1.0) as f32;
+                let cy = ((y0 + (y1 - y0) * fy) / height * 2.0 - 1.0) as f32;
+                let (px, py, _) = bend(cx, cy, curvature);
+                min = [min[0].min(px), min[1].min(py)];
+                max = [max[0].max(px), max[1].max(py)];
+            }
+        }
+        let to_pixels = |c: f32, size: f64| (c as f64 + 1.0) * 0.5 * size;
+        let (sx0, sy0) = (to_pixels(min[0], width), to_pixels(min[1], height));
+        let (sx1, sy1) = (to_pixels(max[0], width), to_pixels(max[1], height));
+        ([sx0, sy0, sx1 - sx0, sy1 - sy0], [x0, y0, x1 - x0, y1 - y0])
+    })
+}
+
+/// How much to scale the red and blue channels about the centre of a
+/// `width`-pixel frame for the aberration: red samples further out than
+/// green, so its image shrinks, and blue's grows
+pub fn aberration_scales(width: usize, params: &CrtParams) -> (f32, f32) {
+    let aberration = params.aberration / width as f32 * 2.0;
+    (1.0 / (1.0 + aberration), 1.0 / (1.0 - aberration))
+}
+
+/// Scanlines and vignette without the warp, as black RGBA8 whose alpha is
+/// how much to darken each pixel. Drawn over a frame it matches `apply`
+/// with no curvature, aberration or flicker.
+pub fn overlay(dst: &mut [u8], width: usize, height: usize, params: &CrtParams) {
+    for y in 0..height {
+        for x in 0..width {
+            let (_, py, r2) = distort(x, y, width, height, 0.0);
+            let shade = 1.0 - darkening(py, r2, height, params);
+            dst[(y * width + x) * 4..][..4].copy_from_slice(&[0, 0, 0, (shade * 255.0).round() as u8]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_matches_the_flat_effect() {
+        let (width, height) = (32, 24);
+        let params = CrtParams { curvature: 0.0, aberration: 0.0, line_height: 2.0, ..CrtParams::default() };
+        let white = vec![255; width * height * 4];
+        let (mut crt, mut shade) = (vec![0; white.len()], vec![0; white.len()]);
+        apply(&white, &mut crt, width, height, &params);
+        overlay(&mut shade, width, height, &params);
+        for (crt, shade) in crt.chunks_exact(4).zip(shade.chunks_exact(4)) {
+            assert!((crt[1] as i32 - (255 - shade[3] as i32)).abs() <= 1);
+        }
+        // Every other pair of rows is a scanline
+        let centre = |y: usize| shade[(y * width + width / 2) * 4 + 3];
+        assert!(centre(height / 2) < centre(height / 2 + 2));
+    }
+
+    #[test]
+    fn warp_tiles_cover_the_frame_and_bend_outwards() {
+        let (width, height) = (640.0, 480.0);
+        let flat: Vec<_> = warp_tiles(width, height, 8, 6, 0.0).collect();
+        assert_eq!(flat.len(), 48);
+        for (src, dst) in &flat {
+            assert!(src.iter().zip(dst).all(|(s, d)| (s - d).abs() < 1e-3), "{src:?} {dst:?}");
+        }
+        let area: f64 = flat.iter().map(|(_, [.., w, h])| w * h).sum();
+        assert_eq!(area, width * height);
+
+        let bent: Vec<_> = warp_tiles(width, height, 8, 6, 0.06).collect();
+        let (src, dst) = bent[0];
+        assert!(src[0] < dst[0] && src[1] < dst[1], "the corner samples from past the corner");
+        let (src, dst) = bent[47];
+        assert!(src[0] + src[2] > dst[0] + dst[2] && src[1] + src[3] > dst[1] + dst[3]);
+    }
+
+    #[test]
+    fn red_and_blue_split_apart() {
+        let (red, blue) = aberration_scales(640, &CrtParams::default());
+        assert!(red < 1.0 && blue > 1.0);
+        let none = CrtParams { aberration: 0.0, ..CrtParams::default() };
+        assert_eq!(aberration_scales(640, &none), (1.0, 1.0));
+    }
+}
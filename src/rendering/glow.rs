@@ -0,0 +1,52 @@
+//! Bloom for the neon look
+//!
+//! Emissive objects are drawn a second time into an offscreen glow layer
+//! at reduced resolution. The backend blurs that layer and adds it on top
+//! of the scene, so bright things bleed light into their surroundings and
+//! overlapping glows build up.
+
+use crate::settings::Choice;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum GlowQuality {
+    Off,
+    Low,
+    #[default]
+    High,
+}
+
+/// How a backend should build and composite the glow layer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlowParams {
+    /// Glow layer is `1 / downscale` of the screen in each direction
+    pub downscale: f64,
+    /// Blur radius in screen pixels
+    pub radius: f64,
+    /// Box blur iterations; three are close enough to a gaussian
+    pub passes: u32,
+    /// Multiplier on the blurred layer when it is added to the scene
+    pub intensity: f32,
+}
+
+impl Choice for GlowQuality {
+    const ALL: &'static [Self] = &[GlowQuality::Off, GlowQuality::Low, GlowQuality::High];
+
+    fn label(self) -> &'static str {
+        match self {
+            GlowQuality::Off => "OFF",
+            GlowQuality::Low => "LOW",
+            GlowQuality::High => "HIGH",
+        }
+    }
+}
+
+impl GlowQuality {
+    /// `None` when glow is switched off
+    pub fn params(self) -> Option<GlowParams> {
+        match self {
+            GlowQuality::Off => None,
+            GlowQuality::Low => Some(GlowParams { downscale: 4.0, radius: 8.0, passes: 2, intensity: 1.2 }),
+            GlowQuality::High => Some(GlowParams { downscale: 2.0, radius: 10.0, passes: 3, intensity: 1.4 }),
+        }
+    }
+}
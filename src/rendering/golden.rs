@@ -0,0 +1,231 @@
+//! Golden screenshot tests
+//!
+//! Each scenario runs the simulation headless with a fixed seed, renders
+//! the final frame through `SoftwareBackend` and compares it with
+//! `tests/golden/<name>.png`. A pixel matches when every channel is within
+//! `CHANNEL_TOLERANCE`; a scenario fails when more than `MAX_MISMATCH` of
+//! its pixels don't.
+//!
+//! After an intended visual change, regenerate the images with
+//! `UPDATE_GOLDEN=1 cargo test golden` and review them in the diff. On
+//! failure the actual frame and a diff image are written to
+//! `target/golden-failures/`.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::constants::*;
+use crate::game::achievements::Achievement;
+use crate::game::GameState;
+use crate::rendering::software::{Framebuffer, SoftwareBackend};
+use crate::rendering::Renderer;
+use crate::ui::{DebugOverlay, OptionsMenu, RunSummary};
+
+const CHANNEL_TOLERANCE: u8 = 8;
+const MAX_MISMATCH: f64 = 0.005;
+const TICK: f64 = 1.0 / 60.0;
+const SEED: u64 = 0x5EED;
+
+/// Run `ticks` updates, calling `input` before each one, and render the result
+fn run(ticks: u32, options: &OptionsMenu, input: impl FnMut(u32, &mut GameState)) -> Framebuffer {
+    run_at(WORLD_WIDTH as usize, WORLD_HEIGHT as usize, ticks, options, input)
+}
+
+/// `run` onto a `width` x `height` backing store instead of one world pixel per pixel
+fn run_at(width: usize, height: usize, ticks: u32, options: &OptionsMenu, input: impl FnMut(u32, &mut GameState)) -> Framebuffer {
+    run_with_debug(width, height, ticks, options, &mut DebugOverlay::default(), input)
+}
+
+/// `run_at`, feeding every tick's frame time to `debug` as the game loop does
+fn run_with_debug(
+    width: usize,
+    height: usize,
+    ticks: u32,
+    options: &OptionsMenu,
+    debug: &mut DebugOverlay,
+    mut input: impl FnMut(u32, &mut GameState),
+) -> Framebuffer {
+    let mut state = GameState::with_seed(SEED);
+    for tick in 0..ticks {
+        input(tick, &mut state);
+        state.update(TICK);
+        debug.record_frame(TICK);
+    }
+
+    let target = Rc::new(RefCell::new(Framebuffer::new(width, height)));
+    let mut renderer = Renderer::with_seed(Box::new(SoftwareBackend::new(target.clone())), SEED);
+    renderer.render(&state, options, debug, &RunSummary::default());
+    let frame = target.borrow().clone();
+    frame
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn write_png(path: &Path, frame: &Framebuffer) {
+    let file = File::create(path).unwrap_or_else(|e| panic!("create {}: {e}", path.display()));
+    let mut encoder = png::Encoder::new(BufWriter::new(file), frame.width as u32, frame.height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().and_then(|mut w| w.write_image_data(&frame.pixels)).unwrap();
+}
+
+fn read_png(path: &Path) -> Framebuffer {
+    let file = File::open(path).unwrap_or_else(|e| {
+        panic!("missing golden {} ({e}); run with UPDATE_GOLDEN=1 to create it", path.display())
+    });
+    let mut reader = png::Decoder::new(file).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Rgba, "{} is not RGBA", path.display());
+    pixels.truncate(info.buffer_size());
+    Framebuffer { width: info.width as usize, height: info.height as usize, pixels }
+}
+
+fn assert_matches_golden(name: &str, actual: &Framebuffer) {
+    let path = golden_dir().join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        write_png(&path, actual);
+        return;
+    }
+
+    let expected = read_png(&path);
+    assert_eq!(
+        (expected.width, expected.height),
+        (actual.width, actual.height),
+        "{name}: frame size changed"
+    );
+
+    let mut diff = Framebuffer::new(actual.width, actual.height);
+    let mut mismatched = 0;
+    for ((e, a), d) in expected
+        .pixels
+        .chunks_exact(4)
+        .zip(actual.pixels.chunks_exact(4))
+        .zip(diff.pixels.chunks_exact_mut(4))
+    {
+        if e.iter().zip(a).any(|(e, a)| e.abs_diff(*a) > CHANNEL_TOLERANCE) {
+            mismatched += 1;
+            d.copy_from_slice(&[255, 0, 255, 255]);
+        } else {
+            // Faded actual frame, so the mismatches stand out
+            d.copy_from_slice(&[a[0] / 4, a[1] / 4, a[2] / 4, 255]);
+        }
+    }
+
+    let ratio = mismatched as f64 / (actual.width * actual.height) as f64;
+    if ratio > MAX_MISMATCH {
+        let out = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/golden-failures");
+        std::fs::create_dir_all(&out).unwrap();
+        write_png(&out.join(format!("{name}.actual.png")), actual);
+        write_png(&out.join(format!("{name}.diff.png")), &diff);
+        panic!(
+            "{name}: {mismatched} pixels ({:.2}%) differ from the golden image; see {}",
+            ratio * 100.0,
+            out.display()
+        );
+    }
+}
+
+#[test]
+fn golden_opening() {
+    let frame = run(90, &OptionsMenu::default(), |_, _| {});
+    assert_matches_golden("opening", &frame);
+}
+
+#[test]
+fn golden_combat() {
+    // Keep firing while circling; gives threats, projectiles, explosions and HUD combo
+    let frame = run(600, &OptionsMenu::default(), |tick, state| {
+        if tick % 12 == 0 {
+            state.shoot();
+        }
+    });
+    assert_matches_golden("combat", &frame);
+}
+
+#[test]
+fn golden_projectiles_visible() {
+    // A volley in flight right after firing, the case the renderer comments worry about
+    let frame = run(20, &OptionsMenu::default(), |tick, state| {
+        if tick % 4 == 0 {
+            state.shoot();
+        }
+    });
+    let projectile_colored = frame
+        .pixels
+        .chunks_exact(4)
+        .filter(|p| p[0] > 200 && p[1] > 120 && p[2] < 80)
+        .count();
+    assert!(projectile_colored > 20, "projectiles barely visible: {projectile_colored} px");
+    assert_matches_golden("projectiles", &frame);
+}
+
+#[test]
+fn golden_options_menu() {
+    let mut options = OptionsMenu::default();
+    options.toggle();
+    options.move_selection(1);
+    let frame = run(30, &options, |_, _| {});
+    assert_matches_golden("options_menu", &frame);
+}
+
+#[test]
+fn golden_debug_overlay() {
+    let mut debug = DebugOverlay::default();
+    debug.toggle();
+    debug.move_selection(2);
+    let (width, height) = (WORLD_WIDTH as usize, WORLD_HEIGHT as usize);
+    let frame = run_with_debug(width, height, 240, &OptionsMenu::default(), &mut debug, |tick, state| {
+        if tick % 8 == 0 {
+            state.shoot();
+        }
+    });
+    assert_matches_golden("debug_overlay", &frame);
+}
+
+#[test]
+fn golden_game_over() {
+    let frame = run(2, &OptionsMenu::default(), |tick, state| {
+        if tick == 1 {
+            state.score = 1230;
+            state.planet_health = 0;
+            state.game_over = true;
+        }
+    });
+    assert_matches_golden("game_over", &frame);
+}
+
+#[test]
+fn golden_achievement_toast() {
+    let frame = run(150, &OptionsMenu::default(), |tick, state| {
+        if tick == 100 {
+            state.unlock(Achievement::ChainReaction);
+        }
+    });
+    assert_matches_golden("achievement_toast", &frame);
+}
+
+#[test]
+fn golden_letterboxed_hidpi() {
+    // A wide window on a 2x screen: world scaled up 1.5x, bars left and right
+    let frame = run_at(1600, 900, 120, &OptionsMenu::default(), |tick, state| {
+        if tick % 12 == 0 {
+            state.shoot();
+        }
+    });
+    let bar = &frame.pixels[..(1600 - 1200) / 2 * 4];
+    assert!(bar.chunks_exact(4).all(|p| p[..3] == [0, 0, 0]), "letterbox bar is not black");
+    assert_matches_golden("letterboxed_hidpi", &frame);
+}
+
+#[test]
+fn simulation_is_deterministic() {
+    let a = run(300, &OptionsMenu::default(), |tick, state| if tick % 10 == 0 { state.shoot() });
+    let b = run(300, &OptionsMenu::default(), |tick, state| if tick % 10 == 0 { state.shoot() });
+    assert!(a == b, "same seed and inputs rendered different frames");
+}
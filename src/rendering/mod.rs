@@ -1,3 +1,17 @@
+pub mod backend;
+pub mod camera;
+pub mod canvas2d;
+pub mod crt;
+pub mod font;
+pub mod glow;
 pub mod renderer;
+pub mod settings;
+pub mod software;
+pub mod viewport;
+pub mod webgl;
 
-pub use renderer::Renderer;
\ No newline at end of file
+#[cfg(test)]
+mod golden;
+
+pub use backend::RenderBackend;
+pub use renderer::Renderer;
@@ -1,7 +1,20 @@
 // src/rendering/renderer.rs
-use web_sys::CanvasRenderingContext2d;
-use wasm_bindgen::JsValue;
+use std::f64::consts::PI;
+use crate::audio::PreloadProgress;
 use crate::game::GameState; // Make sure GameState is imported if used by methods
+use crate::game::achievements::{self, Achievement, TOAST_SECS};
+use crate::game::ecs::{Group, Renderable, Team};
+use crate::rendering::backend::{BlendMode, Color, RenderBackend, TextAlign};
+use crate::rendering::camera::Camera;
+use crate::rendering::crt::{self, CrtParams};
+use crate::rendering::glow::GlowParams;
+use crate::rendering::settings;
+use crate::settings::Choice;
+use crate::rendering::viewport::Viewport;
+use crate::rng::Rng;
+use crate::ui::debug::{DebugOverlay, FRAME_HISTORY, TWEAKS};
+use crate::ui::options::{OptionItem, OptionsMenu};
+use crate::ui::summary::{RunSummary, SummaryPage};
 use crate::constants::*;
 
 // Define a simple struct for stars
@@ -13,35 +26,50 @@ struct Star {
 }
 
 pub struct Renderer {
-    ctx: CanvasRenderingContext2d,
+    backend: Box<dyn RenderBackend>,
     stars: Vec<Star>, // ADDED: Store star positions
+    /// Star layout and CRT flicker; seeded for screenshot tests
+    rng: Rng,
+    viewport: Viewport,
 }
 
 impl Renderer {
-    pub fn new(ctx: CanvasRenderingContext2d) -> Self {
+    pub fn new(backend: Box<dyn RenderBackend>) -> Self {
+        Self::with_rng(backend, Rng::from_entropy())
+    }
+
+    /// Reproducible starfield and shake, for comparing frames across runs
+    pub fn with_seed(backend: Box<dyn RenderBackend>, seed: u64) -> Self {
+        Self::with_rng(backend, Rng::new(seed))
+    }
+
+    fn with_rng(backend: Box<dyn RenderBackend>, mut rng: Rng) -> Self {
         let mut stars = Vec::new();
         let num_stars = 50; // Or any number you like
         for _ in 0..num_stars {
             stars.push(Star {
-                x: (js_sys::Math::random() * CANVAS_WIDTH).floor(),
-                y: (js_sys::Math::random() * CANVAS_HEIGHT * 0.9).floor(), // Allow stars a bit lower
-                size: js_sys::Math::random() * 1.5 + 0.5,
+                x: rng.range(0.0, WORLD_WIDTH).floor(),
+                y: rng.range(0.0, WORLD_HEIGHT * 0.9).floor(), // Allow stars a bit lower
+                size: rng.range(0.5, 2.0),
             });
         }
-        Renderer { ctx, stars } // Initialize stars
+        let (width, height) = backend.size();
+        Renderer { backend, stars, rng, viewport: Viewport::fit(width, height) } // Initialize stars
     }
-    
-    pub fn render(&self, state: &GameState) {
-        self.clear();
-        
-        let translation_applied = state.screen_shake > 0.0;
-        if translation_applied {
-             let shake_x = (js_sys::Math::random() - 0.5) * state.screen_shake;
-             let shake_y = (js_sys::Math::random() - 0.5) * state.screen_shake;
-             self.ctx.save();
-             let _ = self.ctx.translate(shake_x, shake_y);
-        }
 
+    /// Follow a change of the backing-store size, in device pixels
+    pub fn resize(&mut self, width: f64, height: f64) {
+        self.backend.resize(width, height);
+        self.viewport = Viewport::fit(width, height);
+    }
+
+    pub fn render(&mut self, state: &GameState, options: &OptionsMenu, debug: &DebugOverlay, summary: &RunSummary) {
+        self.begin_frame();
+
+        let camera = Camera::for_state(state, settings::settings().camera);
+
+        self.backend.push_transform();
+        camera.apply(&mut *self.backend);
         self.draw_background();
         self.draw_grid();
         self.draw_stars(); // Will now use stored stars
@@ -49,138 +77,370 @@ impl Renderer {
         self.draw_orbit();
         self.draw_particles(state);
         self.draw_player(state);
-        self.draw_threats(state);
-        self.draw_projectiles(state);
-        
-        if translation_applied {
-            self.ctx.restore();
+        self.draw_sprites(state);
+        if debug.open {
+            self.draw_collision_shapes(state);
+        }
+        self.backend.pop_transform();
+
+        if let Some(params) = settings::settings().glow.params() {
+            // Blur radius is in screen pixels; keep the glow the same size in the world
+            self.backend.begin_glow(GlowParams { radius: params.radius * self.viewport.scale, ..params });
+            self.backend.push_transform();
+            camera.apply(&mut *self.backend);
+            self.draw_glow_sources(state);
+            self.backend.pop_transform();
+            self.backend.end_glow();
         }
-        
+
         self.draw_ui(state);
-        
+        self.draw_toast(state);
+
         if state.game_over {
-            self.draw_game_over(state);
+            self.draw_game_over(state, summary);
+        }
+
+        if options.achievements {
+            self.draw_achievements();
+        } else if options.open {
+            self.draw_options(options);
+        }
+
+        if debug.open {
+            self.draw_debug(state, debug);
+        }
+
+        self.finish_frame();
+    }
+
+    pub fn draw_loading(&mut self, progress: PreloadProgress) {
+        self.begin_frame();
+        let bar_width = 300.0;
+        let bar_x = (WORLD_WIDTH - bar_width) / 2.0;
+        let bar_y = WORLD_HEIGHT / 2.0;
+        let red = Color::hex(0xFF0000);
+
+        self.backend.text(&format!("LOADING AUDIO {}/{}", progress.loaded, progress.total), WORLD_WIDTH / 2.0, bar_y - 20.0, 18.0, TextAlign::Center, red);
+        self.backend.fill_rect(bar_x, bar_y, bar_width, 12.0, red.with_alpha(0.15));
+        self.backend.fill_rect(bar_x, bar_y, bar_width * progress.fraction(), 12.0, red);
+        self.finish_frame();
+    }
+
+    /// Clear the whole backing store and map world coordinates onto it
+    fn begin_frame(&mut self) {
+        self.backend.begin_frame();
+        self.clear();
+        let viewport = self.viewport;
+        self.backend.push_transform();
+        self.backend.translate(viewport.offset_x, viewport.offset_y);
+        self.backend.scale(viewport.scale, viewport.scale);
+    }
+
+    /// Black out the letterbox bars, covering anything drawn past the world
+    /// edge, then the CRT pass last, so the whole frame - HUD and menus
+    /// included - is on the tube
+    fn finish_frame(&mut self) {
+        self.backend.pop_transform();
+        for (x, y, w, h) in self.viewport.bars() {
+            self.backend.fill_rect(x, y, w, h, Color::BLACK);
+        }
+
+        if settings::settings().crt {
+            let flicker = if self.rng.next_f64() < 0.1 { crt::FLICKER } else { 0.0 };
+            let defaults = CrtParams::default();
+            let scale = self.viewport.scale as f32;
+            self.backend.apply_crt(CrtParams {
+                aberration: defaults.aberration * scale,
+                line_height: scale.max(1.0),
+                brightness: 1.0 - flicker,
+                ..defaults
+            });
         }
+        self.backend.end_frame();
     }
-    
-    fn clear(&self) {
-        self.ctx.set_fill_style(&JsValue::from_str("#000000"));
-        self.ctx.fill_rect(0.0, 0.0, CANVAS_WIDTH, CANVAS_HEIGHT);
+
+    fn clear(&mut self) {
+        self.backend.clear(Color::BLACK);
     }
-    
+
     fn draw_background(&self) { /* Cleared by self.clear() */ }
-    
-    fn draw_grid(&self) {
-        self.ctx.set_stroke_style(&JsValue::from_str("rgba(255, 0, 100, 0.1)"));
-        self.ctx.set_line_width(1.0);
+
+    fn draw_grid(&mut self) {
+        let color = Color::rgba(255, 0, 100, 0.1);
         for i in 0..20 {
-            let y = CANVAS_HEIGHT * 0.7 + (i as f64).powf(1.5) * 8.0;
-            if y < CANVAS_HEIGHT {
-                self.ctx.begin_path(); self.ctx.move_to(0.0, y); self.ctx.line_to(CANVAS_WIDTH, y); let _ = self.ctx.stroke();
+            let y = WORLD_HEIGHT * 0.7 + (i as f64).powf(1.5) * 8.0;
+            if y < WORLD_HEIGHT {
+                self.backend.line(0.0, y, WORLD_WIDTH, y, 1.0, color);
             }
         }
         for i in 0..40 {
-            let x = (i as f64 - 20.0) * 40.0 + CANVAS_WIDTH / 2.0;
-            self.ctx.begin_path(); self.ctx.move_to(x, CANVAS_HEIGHT * 0.7); self.ctx.line_to(x + (x - CANVAS_WIDTH / 2.0) * 0.3, CANVAS_HEIGHT); let _ = self.ctx.stroke();
+            let x = (i as f64 - 20.0) * 40.0 + WORLD_WIDTH / 2.0;
+            self.backend.line(x, WORLD_HEIGHT * 0.7, x + (x - WORLD_WIDTH / 2.0) * 0.3, WORLD_HEIGHT, 1.0, color);
         }
     }
-    
+
     // MODIFIED: draw_stars now iterates over the stored self.stars
-    fn draw_stars(&self) {
-        self.ctx.set_fill_style(&JsValue::from_str("rgba(255, 255, 255, 0.7)")); // Slightly less opaque
+    fn draw_stars(&mut self) {
+        let color = Color::rgba(255, 255, 255, 0.7); // Slightly less opaque
         for star in &self.stars {
-            self.ctx.fill_rect(star.x, star.y, star.size, star.size);
-        }
-    }
-    
-    fn draw_planet(&self, _state: &GameState) { 
-        self.ctx.set_fill_style(&JsValue::from_str("rgba(200, 0, 0, 0.2)"));
-        self.ctx.begin_path(); let _ = self.ctx.arc(CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0, PLANET_RADIUS * 2.0, 0.0, std::f64::consts::PI * 2.0); let _ = self.ctx.fill();
-        self.ctx.set_fill_style(&JsValue::from_str("#0a0a0a"));
-        self.ctx.begin_path(); let _ = self.ctx.arc(CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0, PLANET_RADIUS, 0.0, std::f64::consts::PI * 2.0); let _ = self.ctx.fill();
-        self.ctx.set_stroke_style(&JsValue::from_str("rgba(200, 0, 50, 0.5)"));
-        self.ctx.set_line_width(1.0); let _ = self.ctx.stroke();
-    }
-    
-    fn draw_orbit(&self) {
-        self.ctx.set_stroke_style(&JsValue::from_str("rgba(100, 0, 0, 0.2)"));
-        self.ctx.set_line_width(1.0); self.ctx.begin_path();
-        let _ = self.ctx.arc(CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0, ORBIT_RADIUS, 0.0, std::f64::consts::PI * 2.0); let _ = self.ctx.stroke();
-    }
-    
-    fn draw_particles(&self, state: &GameState) {
-        for particle in &state.particles {
-            let alpha = particle.lifetime / particle.max_lifetime;
-            self.ctx.set_fill_style(&JsValue::from_str(&format!("rgba({}, {}, {}, {})", particle.color.0, particle.color.1, particle.color.2, alpha)));
-            self.ctx.fill_rect(particle.x - particle.size / 2.0, particle.y - particle.size / 2.0, particle.size, particle.size);
-        }
-    }
-    
-    fn draw_player(&self, state: &GameState) {
+            self.backend.fill_rect(star.x, star.y, star.size, star.size, color);
+        }
+    }
+
+    fn draw_planet(&mut self, _state: &GameState) {
+        let (cx, cy) = (WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        self.backend.fill_circle(cx, cy, PLANET_RADIUS * 2.0, Color::rgba(200, 0, 0, 0.2));
+        self.backend.fill_circle(cx, cy, PLANET_RADIUS, Color::hex(0x0a0a0a));
+        self.backend.stroke_circle(cx, cy, PLANET_RADIUS, 1.0, Color::rgba(200, 0, 50, 0.5));
+    }
+
+    fn draw_orbit(&mut self) {
+        self.backend.stroke_circle(WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0, ORBIT_RADIUS, 1.0, Color::rgba(100, 0, 0, 0.2));
+    }
+
+    fn draw_particles(&mut self, state: &GameState) {
+        // Smoke and debris paint over; light-emitting effects are additive
+        // so dense explosions bloom instead of muddying
+        self.draw_particle_layer(state, false);
+        self.with_blend(BlendMode::Additive, |renderer| renderer.draw_particle_layer(state, true));
+    }
+
+    /// Particles whose preset blends additively (or not), styled by age
+    fn draw_particle_layer(&mut self, state: &GameState, additive: bool) {
+        let world = &state.world;
+        for entity in world.iter() {
+            let Some(&Renderable::Particle { effect, size: scale }) = world.renderables.get(entity) else { continue };
+            let (Some(at), Some(lifetime)) = (world.transforms.get(entity), world.lifetimes.get(entity)) else { continue };
+            let preset = effect.preset();
+            if preset.additive != additive {
+                continue;
+            }
+            let life = lifetime.age() as f32;
+            let size = preset.size_at(life) as f64 * scale;
+            let (r, g, b) = preset.color_at(life);
+            self.backend.fill_rect(at.x - size / 2.0, at.y - size / 2.0, size, size, Color::rgba(r, g, b, preset.alpha_at(life)));
+        }
+    }
+
+    fn draw_player(&mut self, state: &GameState) {
+        self.draw_ship(state, Color::hex(0xcccccc));
+    }
+
+    fn draw_ship(&mut self, state: &GameState, color: Color) {
         let (player_x, player_y) = state.player.get_position();
-        self.ctx.set_fill_style(&JsValue::from_str("rgba(255, 0, 0, 0.3)"));
-        self.ctx.begin_path(); let _ = self.ctx.arc(player_x, player_y, PLAYER_SIZE, 0.0, std::f64::consts::PI * 2.0); let _ = self.ctx.fill();
-        self.ctx.set_fill_style(&JsValue::from_str("#cccccc"));
-        self.ctx.save();
-        let _ = self.ctx.translate(player_x, player_y);
-        let _ = self.ctx.rotate(state.player.angle + std::f64::consts::PI / 2.0);
-        self.ctx.begin_path(); self.ctx.move_to(0.0, -PLAYER_SIZE / 2.0);
-        self.ctx.line_to(-PLAYER_SIZE / 3.0, PLAYER_SIZE / 2.0); self.ctx.line_to(PLAYER_SIZE / 3.0, PLAYER_SIZE / 2.0);
-        self.ctx.close_path(); let _ = self.ctx.fill();
-        self.ctx.restore();
-    }
-    
-    fn draw_threats(&self, state: &GameState) {
-        for threat in &state.threats {
-            self.ctx.set_fill_style(&JsValue::from_str("rgba(200, 0, 0, 0.4)"));
-            self.ctx.begin_path(); let _ = self.ctx.arc(threat.x, threat.y, threat.radius * 1.5, 0.0, std::f64::consts::PI * 2.0); let _ = self.ctx.fill();
-            self.ctx.set_fill_style(&JsValue::from_str("#660000"));
-            self.ctx.begin_path(); let _ = self.ctx.arc(threat.x, threat.y, threat.radius, 0.0, std::f64::consts::PI * 2.0); let _ = self.ctx.fill();
-        }
-    }
-    
-    fn draw_projectiles(&self, state: &GameState) {
-        if state.projectiles.is_empty() && state.time < 5.0 { // Log only early if no projectiles
-            // web_sys::console::log_1(&"draw_projectiles called, no projectiles to draw".into());
-        }
-        for proj in &state.projectiles {
-            // web_sys::console::log_1(&format!("Drawing projectile at: ({}, {})", proj.x, proj.y).into());
-            self.ctx.set_fill_style(&JsValue::from_str("rgba(255, 100, 0, 0.8)")); // Brighter for visibility
-            self.ctx.begin_path(); 
-            let _ = self.ctx.arc(proj.x, proj.y, proj.radius * 1.5, 0.0, std::f64::consts::PI * 2.0); // Slightly larger apparent radius
-            let _ = self.ctx.fill();
-            
-            self.ctx.set_fill_style(&JsValue::from_str("#ffaa00")); // Brighter core
-            self.ctx.begin_path(); 
-            let _ = self.ctx.arc(proj.x, proj.y, proj.radius, 0.0, std::f64::consts::PI * 2.0); 
-            let _ = self.ctx.fill();
-        }
-    }
-    
-    fn draw_ui(&self, state: &GameState) {
-        self.ctx.set_fill_style(&JsValue::from_str("#FFFFFF"));
-        self.ctx.set_font("20px Arial");
-        let _ = self.ctx.fill_text(&format!("Score: {}", state.score), 10.0, 30.0);
-        let _ = self.ctx.fill_text(&format!("Planet Health: {}", state.planet_health), 10.0, 60.0);
+        self.backend.push_transform();
+        self.backend.translate(player_x, player_y);
+        self.backend.rotate(state.player.angle + PI / 2.0);
+        self.backend.fill_polygon(
+            &[(0.0, -PLAYER_SIZE / 2.0), (-PLAYER_SIZE / 3.0, PLAYER_SIZE / 2.0), (PLAYER_SIZE / 3.0, PLAYER_SIZE / 2.0)],
+            color,
+        );
+        self.backend.pop_transform();
+    }
+
+    /// Every entity with a body (not particles), in slot order
+    fn draw_sprites(&mut self, state: &GameState) {
+        let world = &state.world;
+        for entity in world.iter() {
+            let (Some(renderable), Some(at), Some(collider)) =
+                (world.renderables.get(entity), world.transforms.get(entity), world.colliders.get(entity))
+            else {
+                continue;
+            };
+            let (x, y, radius) = (at.x, at.y, collider.radius);
+            match renderable {
+                Renderable::Threat => self.backend.fill_circle(x, y, radius, Color::hex(0x660000)),
+                Renderable::Projectile => self.backend.fill_circle(x, y, radius, Color::hex(0xffaa00)), // Brighter core
+                Renderable::Particle { .. } => {}
+            }
+        }
+    }
+
+    /// Everything that emits light, drawn again into the glow layer in its
+    /// emission colour. The bloom replaces the old translucent halos.
+    fn draw_glow_sources(&mut self, state: &GameState) {
+        let (cx, cy) = (WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        self.backend.stroke_circle(cx, cy, PLANET_RADIUS, 3.0, Color::rgb(200, 0, 50));
+        self.draw_ship(state, Color::rgb(255, 40, 40));
+        let world = &state.world;
+        for entity in world.iter() {
+            let (Some(renderable), Some(at), Some(collider)) =
+                (world.renderables.get(entity), world.transforms.get(entity), world.colliders.get(entity))
+            else {
+                continue;
+            };
+            let (x, y, radius) = (at.x, at.y, collider.radius);
+            match renderable {
+                Renderable::Threat => self.backend.fill_circle(x, y, radius, Color::rgb(220, 0, 0)),
+                Renderable::Projectile => self.backend.fill_circle(x, y, radius * 1.2, Color::rgb(255, 100, 0)),
+                Renderable::Particle { .. } => {}
+            }
+        }
+        self.with_blend(BlendMode::Additive, |renderer| renderer.draw_particle_layer(state, true));
+    }
+
+    fn draw_ui(&mut self, state: &GameState) {
+        let white = Color::WHITE;
+        self.backend.text(&format!("Score: {}", state.score), 10.0, 30.0, 20.0, TextAlign::Left, white);
+        self.backend.text(&format!("Planet Health: {}", state.planet_health), 10.0, 60.0, 20.0, TextAlign::Left, white);
+        self.backend.text(&format!("Wave {}", state.wave), WORLD_WIDTH - 10.0, 30.0, 20.0, TextAlign::Right, white);
+        if state.wave_banner_timer > 0.0 {
+            self.backend.text(&format!("WAVE {} CLEARED", state.wave - 1), WORLD_WIDTH / 2.0, WORLD_HEIGHT * 0.25, 32.0, TextAlign::Center, Color::hex(0xFF0066));
+        }
         if state.combo > 1 {
-            self.ctx.set_fill_style(&JsValue::from_str("#FFD700"));
-            self.ctx.set_font("24px Arial");
-            let _ = self.ctx.fill_text(&format!("{}x COMBO!", state.combo), 10.0, 90.0);
-        }
-    }
-    
-    fn draw_game_over(&self, state: &GameState) {
-        self.ctx.set_fill_style(&JsValue::from_str("rgba(0, 0, 0, 0.7)"));
-        self.ctx.fill_rect(0.0, 0.0, CANVAS_WIDTH, CANVAS_HEIGHT);
-        self.ctx.set_fill_style(&JsValue::from_str("#FF0000"));
-        self.ctx.set_font("48px Arial");
-        self.ctx.set_text_align("center");
-        let _ = self.ctx.fill_text("GAME OVER", CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0 - 50.0);
-        self.ctx.set_fill_style(&JsValue::from_str("#FFFFFF"));
-        self.ctx.set_font("24px Arial");
-        let _ = self.ctx.fill_text(&format!("Final Score: {}", state.score), CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0 + 10.0);
-        self.ctx.set_font("16px Arial");
-        let _ = self.ctx.fill_text("Press F5 to restart", CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0 + 50.0);
-        self.ctx.set_text_align("left");
-    }
-}
\ No newline at end of file
+            self.backend.text(&format!("{}x COMBO!", state.combo), 10.0, 90.0, 24.0, TextAlign::Left, Color::hex(0xFFD700));
+        }
+    }
+
+    fn draw_game_over(&mut self, state: &GameState, summary: &RunSummary) {
+        self.backend.fill_rect(0.0, 0.0, WORLD_WIDTH, WORLD_HEIGHT, Color::rgba(0, 0, 0, 0.7));
+        let (cx, cy) = (WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        self.backend.text("GAME OVER", cx, cy - 130.0, 48.0, TextAlign::Center, Color::hex(0xFF0000));
+
+        let page = summary.current();
+        let heading = format!("{} ({}/{})", page.title(), summary.page + 1, SummaryPage::ALL.len());
+        self.backend.text(&heading, cx, cy - 70.0, 20.0, TextAlign::Center, Color::hex(0xFFD700));
+        for (i, (label, value)) in page.rows(state).iter().enumerate() {
+            let y = cy - 30.0 + i as f64 * 28.0;
+            self.backend.text(label, cx - 160.0, y, 20.0, TextAlign::Left, Color::hex(0xCCCCCC));
+            self.backend.text(value, cx + 160.0, y, 20.0, TextAlign::Right, Color::WHITE);
+        }
+
+        self.backend.text("LEFT/RIGHT: PAGE // E: EXPORT JSON // F5: RESTART", cx, cy + 150.0, 14.0, TextAlign::Center, Color::hex(0x888888));
+    }
+
+    fn draw_options(&mut self, options: &OptionsMenu) {
+        self.backend.fill_rect(0.0, 0.0, WORLD_WIDTH, WORLD_HEIGHT, Color::rgba(0, 0, 0, 0.8));
+        let (cx, cy) = (WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        self.backend.text("OPTIONS", cx, cy - 140.0, 36.0, TextAlign::Center, Color::hex(0xFF0000));
+
+        for (i, item) in OptionItem::ALL.iter().enumerate() {
+            let y = cy - 100.0 + i as f64 * 28.0;
+            let color = if i == options.selected { Color::hex(0xFFD700) } else { Color::hex(0xCCCCCC) };
+            self.backend.text(item.label(), cx - 160.0, y, 20.0, TextAlign::Left, color);
+            self.backend.text(&item.value(), cx + 160.0, y, 20.0, TextAlign::Right, color);
+        }
+
+        self.backend.text("UP/DOWN: SELECT // LEFT/RIGHT: ADJUST // ESC: CLOSE", cx, cy + 180.0, 14.0, TextAlign::Center, Color::hex(0x888888));
+    }
+
+    /// Every achievement, unlocked ones lit
+    fn draw_achievements(&mut self) {
+        self.backend.fill_rect(0.0, 0.0, WORLD_WIDTH, WORLD_HEIGHT, Color::rgba(0, 0, 0, 0.85));
+        let (cx, cy) = (WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        let unlocked = achievements::progress();
+        let heading = format!("ACHIEVEMENTS {}/{}", unlocked.len(), Achievement::ALL.len());
+        self.backend.text(&heading, cx, cy - 140.0, 36.0, TextAlign::Center, Color::hex(0xFF0000));
+
+        for (i, achievement) in Achievement::ALL.into_iter().enumerate() {
+            let y = cy - 90.0 + i as f64 * 48.0;
+            let (mark, title, detail) = if unlocked.contains(achievement) {
+                ("*", Color::hex(0xFFD700), Color::hex(0xCCCCCC))
+            } else {
+                ("-", Color::hex(0x666666), Color::hex(0x555555))
+            };
+            self.backend.text(mark, cx - 200.0, y, 20.0, TextAlign::Left, title);
+            self.backend.text(achievement.title(), cx - 175.0, y, 20.0, TextAlign::Left, title);
+            self.backend.text(achievement.description(), cx - 175.0, y + 20.0, 14.0, TextAlign::Left, detail);
+        }
+
+        self.backend.text("ESC: BACK", cx, cy + 180.0, 14.0, TextAlign::Center, Color::hex(0x888888));
+    }
+
+    /// The latest unlock, sliding down from the top edge and back
+    fn draw_toast(&mut self, state: &GameState) {
+        let Some(toast) = state.toast else { return };
+        const SLIDE_SECS: f64 = 0.3;
+        let shown = ((TOAST_SECS - toast.remaining) / SLIDE_SECS).min(toast.remaining / SLIDE_SECS).clamp(0.0, 1.0);
+        let (width, height) = (300.0, 52.0);
+        let (x, y) = (WORLD_WIDTH / 2.0 - width / 2.0, -height + (height + 12.0) * shown);
+        let gold = Color::hex(0xFFD700);
+        self.backend.fill_rect(x, y, width, height, Color::rgba(0, 0, 0, 0.85));
+        self.backend.line(x, y + height, x + width, y + height, 2.0, gold);
+        self.backend.text("ACHIEVEMENT UNLOCKED", WORLD_WIDTH / 2.0, y + 18.0, 12.0, TextAlign::Center, Color::hex(0xCCCCCC));
+        self.backend.text(toast.achievement.title(), WORLD_WIDTH / 2.0, y + 40.0, 20.0, TextAlign::Center, gold);
+    }
+
+    /// Every collider as an outline; shots in cyan, enemies in green
+    fn draw_collision_shapes(&mut self, state: &GameState) {
+        let world = &state.world;
+        for entity in world.iter() {
+            let (Some(at), Some(collider)) = (world.transforms.get(entity), world.colliders.get(entity)) else { continue };
+            let color = match world.teams.get(entity) {
+                Some(Team::Player) => Color::hex(0x00ffff),
+                _ => Color::hex(0x00ff66),
+            };
+            self.backend.stroke_circle(at.x, at.y, collider.radius, 1.0, color);
+        }
+        self.backend.stroke_circle(WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0, PLANET_RADIUS, 1.0, Color::hex(0x00ff66));
+    }
+
+    /// Stats on the left, the tweak panel on the right
+    fn draw_debug(&mut self, state: &GameState, debug: &DebugOverlay) {
+        let text = Color::hex(0x00ff66);
+        let dim = Color::hex(0x888888);
+        let panel = Color::rgba(0, 0, 0, 0.75);
+
+        // Stats
+        let (x, mut y) = (10.0, 120.0);
+        self.backend.fill_rect(x - 4.0, y - 14.0, 228.0, 242.0, panel);
+        let world = &state.world;
+        let (mut threats, mut projectiles, mut particles) = (0, 0, 0);
+        for entity in world.iter() {
+            match world.renderables.get(entity) {
+                Some(Renderable::Threat) => threats += 1,
+                Some(Renderable::Projectile) => projectiles += 1,
+                Some(Renderable::Particle { .. }) => particles += 1,
+                None => {}
+            }
+        }
+        let last_frame = debug.frame_times().last().unwrap_or(0.0);
+        let adaptive = &state.config.difficulty.adaptive;
+        let lines = [
+            format!("FPS {:.0}  FRAME {:.1} MS", debug.fps(), last_frame * 1000.0),
+            format!("THREATS {threats} / {}", world.capacity(Group::Threats)),
+            format!("PROJECTILES {projectiles} / {}", world.capacity(Group::Projectiles)),
+            format!("PARTICLES {particles} / {}", world.capacity(Group::Particles)),
+            format!("WAVE {}  KILLS {} / {}", state.wave, state.wave_kills, state.config.threats_in_wave(state.wave)),
+            format!("NEXT SPAWN {:.2} S", state.threat_spawn_timer.max(0.0)),
+            format!("PACE {:.0}%  INTERVAL {:.2} S", state.spawn_progression() * 100.0, state.spawn_interval()),
+            format!("{} {}  X{:.2}", state.difficulty.label(), if state.adaptive.enabled { "ADAPTIVE" } else { "FIXED" }, state.adaptive.pace),
+            format!("HIT {:.0}%  PLANET DMG {:.0}/MIN", state.adaptive.accuracy(adaptive) * 100.0, state.adaptive.damage_per_minute(adaptive)),
+        ];
+        for line in &lines {
+            self.backend.text(line, x, y, 12.0, TextAlign::Left, text);
+            y += 16.0;
+        }
+
+        // Frame-time graph: one bar per frame, the line is 60 FPS
+        let (graph_height, max_ms) = (50.0, 50.0);
+        let base = y + graph_height;
+        let bar = 220.0 / FRAME_HISTORY as f64;
+        for (i, dt) in debug.frame_times().enumerate() {
+            let h = (dt * 1000.0 / max_ms).min(1.0) * graph_height;
+            let color = if dt > 1.0 / 50.0 { Color::hex(0xff3333) } else { text };
+            self.backend.fill_rect(x + i as f64 * bar, base - h, bar, h, color);
+        }
+        let target = base - 1000.0 / 60.0 / max_ms * graph_height;
+        self.backend.line(x, target, x + 220.0, target, 1.0, dim);
+
+        // Tweaks
+        let (right, mut y) = (WORLD_WIDTH - 10.0, 50.0);
+        let width = 290.0;
+        self.backend.fill_rect(right - width - 4.0, y - 14.0, width + 8.0, TWEAKS.len() as f64 * 14.0 + 34.0, panel);
+        for (i, tweak) in TWEAKS.iter().enumerate() {
+            let color = if i == debug.selected { Color::hex(0xFFD700) } else { Color::hex(0xCCCCCC) };
+            self.backend.text(tweak.key, right - width, y, 11.0, TextAlign::Left, color);
+            self.backend.text(&tweak.value(&state.config), right, y, 11.0, TextAlign::Right, color);
+            y += 14.0;
+        }
+        self.backend.text("[ ]: SELECT // , .: ADJUST // F3: CLOSE", right - width / 2.0, y + 6.0, 10.0, TextAlign::Center, dim);
+    }
+
+    /// Switch blending for a block of draws, restoring normal blending after
+    fn with_blend(&mut self, mode: BlendMode, draw: impl FnOnce(&mut Self)) {
+        self.backend.set_blend_mode(mode);
+        draw(self);
+        self.backend.set_blend_mode(BlendMode::Normal);
+    }
+}
@@ -0,0 +1,73 @@
+//! Player-facing graphics settings, persisted across sessions
+
+use crate::rendering::camera::CameraMode;
+use crate::rendering::glow::GlowQuality;
+use crate::settings::{self as persist, Choice, Persisted, Persistent};
+
+thread_local! {
+    static SETTINGS: Persistent<GraphicsSettings> = const { Persistent::new() };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GraphicsSettings {
+    pub glow: GlowQuality,
+    /// Scanlines, curvature and the rest of the CRT pass
+    pub crt: bool,
+    pub camera: CameraMode,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        GraphicsSettings { glow: GlowQuality::default(), crt: true, camera: CameraMode::default() }
+    }
+}
+
+/// Saved as `glow=high;crt=1;camera=fixed`
+impl Persisted for GraphicsSettings {
+    const STORAGE_KEY: &'static str = "graphics";
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "glow" => self.glow = GlowQuality::parse(value).unwrap_or(self.glow),
+            "crt" => self.crt = persist::parse_flag(value).unwrap_or(self.crt),
+            "camera" => self.camera = CameraMode::parse(value).unwrap_or(self.camera),
+            _ => {}
+        }
+    }
+
+    fn pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("glow", self.glow.save_value()),
+            ("crt", persist::flag(self.crt)),
+            ("camera", self.camera.save_value()),
+        ]
+    }
+}
+
+pub fn settings() -> GraphicsSettings {
+    SETTINGS.with(Persistent::get)
+}
+
+/// Mutate the settings and persist them; the renderer picks them up next frame
+pub fn update(f: impl FnOnce(&mut GraphicsSettings)) {
+    SETTINGS.with(|s| s.update(f));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let settings = GraphicsSettings { glow: GlowQuality::Low, crt: false, camera: CameraMode::Follow };
+        assert_eq!(GraphicsSettings::parse(&settings.serialize()), settings);
+    }
+
+    #[test]
+    fn garbage_keeps_the_defaults() {
+        let defaults = GraphicsSettings::default();
+        assert_eq!(GraphicsSettings::parse(""), defaults);
+        assert_eq!(GraphicsSettings::parse("glow=ultra;crt=yes;camera=;;=;fov=90"), defaults);
+        assert_eq!(GraphicsSettings::parse("crt=0;glow=OFF").glow, GlowQuality::Off, "labels match in any case");
+    }
+}
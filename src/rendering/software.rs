@@ -0,0 +1,351 @@
+//! CPU `RenderBackend` that rasterizes into an RGBA buffer
+//!
+//! No browser needed, so rendering can be checked in `cargo test` against
+//! golden screenshots. Shapes are turned into screen-space polygons and
+//! filled scanline by scanline, sampling pixel centres with the even-odd
+//! rule. There is no anti-aliasing: output is meant to be exact and
+//! repeatable rather than pretty. Text uses the 5x7 bitmap font. The glow
+//! layer is blurred with repeated box blurs and added with bilinear upscaling.
+
+use std::cell::RefCell;
+use std::f64::consts::TAU;
+use std::rc::Rc;
+use crate::rendering::backend::{BlendMode, Color, RenderBackend, TextAlign, Transform, TransformStack};
+use crate::rendering::crt::{self, CrtParams};
+use crate::rendering::font;
+use crate::rendering::glow::GlowParams;
+
+/// Straight (non-premultiplied) RGBA8 pixels, row-major, top row first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer { width, height, pixels: vec![0; width * height * 4] }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    fn blend(&mut self, x: usize, y: usize, [r, g, b, a]: [f32; 4], mode: BlendMode) {
+        let i = (y * self.width + x) * 4;
+        let dst = &mut self.pixels[i..i + 4];
+        let mix = |d: u8, s: f32| -> u8 {
+            let d = d as f32 / 255.0;
+            let out = match mode {
+                BlendMode::Normal => s * a + d * (1.0 - a),
+                BlendMode::Additive => d + s * a,
+            };
+            (out.clamp(0.0, 1.0) * 255.0).round() as u8
+        };
+        dst[0] = mix(dst[0], r);
+        dst[1] = mix(dst[1], g);
+        dst[2] = mix(dst[2], b);
+        let dst_a = dst[3] as f32 / 255.0;
+        dst[3] = ((a + dst_a * (1.0 - a)).clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+/// The backend draws into this; callers keep a clone to read frames back
+pub type SharedFramebuffer = Rc<RefCell<Framebuffer>>;
+
+/// Offscreen layer between `begin_glow` and `end_glow`
+struct GlowLayer {
+    buffer: Framebuffer,
+    params: GlowParams,
+}
+
+pub struct SoftwareBackend {
+    target: SharedFramebuffer,
+    glow: Option<GlowLayer>,
+    transform: TransformStack,
+    blend: BlendMode,
+}
+
+impl SoftwareBackend {
+    pub fn new(target: SharedFramebuffer) -> Self {
+        SoftwareBackend { target, glow: None, transform: TransformStack::default(), blend: BlendMode::Normal }
+    }
+
+    /// Fill the union of `contours` (already in local space) with the even-odd rule
+    fn fill_contours(&mut self, contours: &[Vec<(f64, f64)>], color: Color) {
+        let t = self.transform.current;
+        let contours: Vec<Vec<(f64, f64)>> = contours
+            .iter()
+            .map(|c| c.iter().map(|&(x, y)| t.apply(x, y)).collect())
+            .collect();
+
+        let mut scene = self.target.borrow_mut();
+        let target = match self.glow.as_mut() {
+            Some(glow) => &mut glow.buffer,
+            None => &mut *scene,
+        };
+        let (width, height) = (target.width as f64, target.height as f64);
+        let ys = contours.iter().flatten().map(|p| p.1);
+        let min_y = ys.clone().fold(f64::INFINITY, f64::min).max(0.0);
+        let max_y = ys.fold(f64::NEG_INFINITY, f64::max).min(height);
+        if min_y >= max_y {
+            return;
+        }
+
+        let color = color.to_f32();
+        let mut crossings = Vec::new();
+        for row in (min_y.floor() as usize)..(max_y.ceil() as usize) {
+            let sample_y = row as f64 + 0.5;
+            crossings.clear();
+            for contour in &contours {
+                for (i, &(x0, y0)) in contour.iter().enumerate() {
+                    let (x1, y1) = contour[(i + 1) % contour.len()];
+                    if (y0 <= sample_y) != (y1 <= sample_y) {
+                        crossings.push(x0 + (sample_y - y0) / (y1 - y0) * (x1 - x0));
+                    }
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                // Pixels whose centre lies inside [span[0], span[1])
+                let start = (span[0] - 0.5).ceil().clamp(0.0, width) as usize;
+                let end = (span[1] - 0.5).ceil().clamp(0.0, width) as usize;
+                for x in start..end {
+                    target.blend(x, row, color, self.blend);
+                }
+            }
+        }
+    }
+
+    /// Same segment count rule as the WebGL backend, so both look alike
+    fn circle_points(&self, x: f64, y: f64, radius: f64) -> Vec<(f64, f64)> {
+        let on_screen = radius * self.transform.current.scale_factor();
+        let segments = (on_screen * 0.7).clamp(8.0, 64.0) as usize;
+        (0..segments)
+            .map(|i| {
+                let angle = i as f64 / segments as f64 * TAU;
+                (x + angle.cos() * radius, y + angle.sin() * radius)
+            })
+            .collect()
+    }
+}
+
+impl RenderBackend for SoftwareBackend {
+    fn begin_frame(&mut self) {
+        self.transform.reset();
+        self.blend = BlendMode::Normal;
+        self.glow = None;
+    }
+
+    fn end_frame(&mut self) {}
+
+    fn size(&self) -> (f64, f64) {
+        let target = self.target.borrow();
+        (target.width as f64, target.height as f64)
+    }
+
+    fn resize(&mut self, width: f64, height: f64) {
+        *self.target.borrow_mut() = Framebuffer::new(width as usize, height as usize);
+    }
+
+    fn clear(&mut self, color: Color) {
+        let [r, g, b, a] = color.to_f32().map(|c| (c * 255.0).round() as u8);
+        let mut scene = self.target.borrow_mut();
+        let target = match self.glow.as_mut() {
+            Some(glow) => &mut glow.buffer,
+            None => &mut *scene,
+        };
+        for pixel in target.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[r, g, b, a]);
+        }
+    }
+
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Color) {
+        self.fill_contours(&[vec![(x, y), (x + w, y), (x + w, y + h), (x, y + h)]], color);
+    }
+
+    fn fill_circle(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+        let points = self.circle_points(x, y, radius.max(0.0));
+        self.fill_contours(&[points], color);
+    }
+
+    fn stroke_circle(&mut self, x: f64, y: f64, radius: f64, width: f64, color: Color) {
+        let outer = self.circle_points(x, y, radius + width / 2.0);
+        let inner = self.circle_points(x, y, (radius - width / 2.0).max(0.0));
+        self.fill_contours(&[outer, inner], color);
+    }
+
+    fn fill_polygon(&mut self, points: &[(f64, f64)], color: Color) {
+        if points.len() >= 3 {
+            self.fill_contours(&[points.to_vec()], color);
+        }
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, width: f64, color: Color) {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let len = dx.hypot(dy);
+        if len == 0.0 {
+            return;
+        }
+        let (nx, ny) = (-dy / len * width / 2.0, dx / len * width / 2.0);
+        self.fill_contours(
+            &[vec![(x1 + nx, y1 + ny), (x2 + nx, y2 + ny), (x2 - nx, y2 - ny), (x1 - nx, y1 - ny)]],
+            color,
+        );
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, align: TextAlign, color: Color) {
+        let px = font::pixel_size(size);
+        let left = match align {
+            TextAlign::Left => x,
+            TextAlign::Center => x - font::text_width(text, size) / 2.0,
+            TextAlign::Right => x - font::text_width(text, size),
+        };
+        let top = y - font::GLYPH_HEIGHT as f64 * px;
+
+        for (i, c) in text.chars().enumerate() {
+            let glyph_x = left + (i * font::ADVANCE) as f64 * px;
+            for (row, bits) in font::glyph(c).iter().enumerate() {
+                for col in 0..font::GLYPH_WIDTH {
+                    if bits & (0x10 >> col) != 0 {
+                        self.fill_rect(glyph_x + col as f64 * px, top + row as f64 * px, px, px, color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend = mode;
+    }
+
+    fn begin_glow(&mut self, params: GlowParams) {
+        let (width, height) = {
+            let scene = self.target.borrow();
+            (scene.width, scene.height)
+        };
+        let size = |n: usize| (n as f64 / params.downscale).ceil().max(1.0) as usize;
+        self.glow = Some(GlowLayer { buffer: Framebuffer::new(size(width), size(height)), params });
+        // Draw in screen space, shrunk onto the smaller layer
+        self.transform.push();
+        let shrink = 1.0 / params.downscale;
+        self.transform.current = Transform::scaling(shrink, shrink).then(&self.transform.current);
+    }
+
+    fn end_glow(&mut self) {
+        let Some(GlowLayer { buffer, params }) = self.glow.take() else { return };
+        self.transform.pop();
+
+        let radius = (params.radius / params.downscale).round().max(1.0) as usize;
+        let mut light = Light::from_framebuffer(&buffer);
+        for _ in 0..params.passes {
+            light.box_blur(radius);
+        }
+        light.add_onto(&mut self.target.borrow_mut(), params.downscale, params.intensity);
+    }
+
+    fn apply_crt(&mut self, params: CrtParams) {
+        let mut scene = self.target.borrow_mut();
+        let source = scene.pixels.clone();
+        let (width, height) = (scene.width, scene.height);
+        crt::apply(&source, &mut scene.pixels, width, height, &params);
+    }
+
+    fn push_transform(&mut self) {
+        self.transform.push();
+    }
+
+    fn pop_transform(&mut self) {
+        self.transform.pop();
+    }
+
+    fn translate(&mut self, x: f64, y: f64) {
+        self.transform.apply(&Transform::translation(x, y));
+    }
+
+    fn rotate(&mut self, angle: f64) {
+        self.transform.apply(&Transform::rotation(angle));
+    }
+
+    fn scale(&mut self, sx: f64, sy: f64) {
+        self.transform.apply(&Transform::scaling(sx, sy));
+    }
+}
+
+/// Glow layer as linear RGB light, for blurring without 8-bit banding.
+/// Drawing onto a transparent layer leaves colour premultiplied by
+/// coverage, which is exactly the amount of light each pixel emits.
+struct Light {
+    width: usize,
+    height: usize,
+    rgb: Vec<[f32; 3]>,
+}
+
+impl Light {
+    fn from_framebuffer(buffer: &Framebuffer) -> Self {
+        let rgb = buffer
+            .pixels
+            .chunks_exact(4)
+            .map(|p| [p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0])
+            .collect();
+        Light { width: buffer.width, height: buffer.height, rgb }
+    }
+
+    /// Horizontal then vertical running-sum box blur, edges clamped
+    fn box_blur(&mut self, radius: usize) {
+        let (w, h) = (self.width, self.height);
+        blur_lines(&mut self.rgb, h, w, w, 1, radius);
+        blur_lines(&mut self.rgb, w, h, 1, w, radius);
+    }
+
+    fn sample(&self, x: f64, y: f64) -> [f32; 3] {
+        let x = x.clamp(0.0, (self.width - 1) as f64);
+        let y = y.clamp(0.0, (self.height - 1) as f64);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (fx, fy) = ((x - x0 as f64) as f32, (y - y0 as f64) as f32);
+        let at = |x: usize, y: usize| self.rgb[y * self.width + x];
+        let lerp = |a: [f32; 3], b: [f32; 3], t: f32| [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t);
+        lerp(lerp(at(x0, y0), at(x1, y0), fx), lerp(at(x0, y1), at(x1, y1), fx), fy)
+    }
+
+    fn add_onto(&self, scene: &mut Framebuffer, downscale: f64, intensity: f32) {
+        for y in 0..scene.height {
+            let ly = (y as f64 + 0.5) / downscale - 0.5;
+            for x in 0..scene.width {
+                let light = self.sample((x as f64 + 0.5) / downscale - 0.5, ly);
+                let i = (y * scene.width + x) * 4;
+                for (dst, l) in scene.pixels[i..i + 3].iter_mut().zip(light) {
+                    let out = *dst as f32 / 255.0 + l * intensity;
+                    *dst = (out.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Box blur `lines` lines of `len` samples each, where a line starts at
+/// `line * line_stride` and its samples are `stride` apart
+fn blur_lines(data: &mut [[f32; 3]], lines: usize, len: usize, line_stride: usize, stride: usize, radius: usize) {
+    let mut line_buf = vec![[0.0f32; 3]; len];
+    let window = (2 * radius + 1) as f32;
+    for line in 0..lines {
+        let start = line * line_stride;
+        for (i, v) in line_buf.iter_mut().enumerate() {
+            *v = data[start + i * stride];
+        }
+        let at = |i: isize| line_buf[i.clamp(0, len as isize - 1) as usize];
+        let mut sum = [0.0f32; 3];
+        for i in -(radius as isize)..=radius as isize {
+            let v = at(i);
+            sum = [sum[0] + v[0], sum[1] + v[1], sum[2] + v[2]];
+        }
+        for i in 0..len {
+            data[start + i * stride] = sum.map(|s| s / window);
+            let (add, sub) = (at(i as isize + radius as isize + 1), at(i as isize - radius as isize));
+            sum = [0, 1, 2].map(|c| sum[c] + add[c] - sub[c]);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Mapping from world coordinates to the backing store
+//!
+//! The simulation and everything the renderer draws live in a fixed
+//! `WORLD_WIDTH` x `WORLD_HEIGHT` space. The canvas behind it can be any
+//! size (CSS size times `devicePixelRatio`), so the world is scaled
+//! uniformly to fit and centred, leaving black bars on the long side.
+
+use crate::constants::{WORLD_HEIGHT, WORLD_WIDTH};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    /// Backing-store pixels per world unit
+    pub scale: f64,
+    /// Top-left corner of the world in backing-store pixels
+    pub offset_x: f64,
+    pub offset_y: f64,
+    /// Full backing-store size, bars included
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Viewport {
+    /// Largest letterboxed fit of the world into a `width` x `height` target
+    pub fn fit(width: f64, height: f64) -> Self {
+        let scale = (width / WORLD_WIDTH).min(height / WORLD_HEIGHT).max(f64::EPSILON);
+        Viewport {
+            scale,
+            offset_x: ((width - WORLD_WIDTH * scale) / 2.0).floor(),
+            offset_y: ((height - WORLD_HEIGHT * scale) / 2.0).floor(),
+            width,
+            height,
+        }
+    }
+
+    /// Backing-store rectangles outside the world: `(x, y, w, h)`, empty ones skipped
+    pub fn bars(&self) -> impl Iterator<Item = (f64, f64, f64, f64)> {
+        let right = self.offset_x + WORLD_WIDTH * self.scale;
+        let bottom = self.offset_y + WORLD_HEIGHT * self.scale;
+        [
+            (0.0, 0.0, self.width, self.offset_y),
+            (0.0, bottom, self.width, self.height - bottom),
+            (0.0, 0.0, self.offset_x, self.height),
+            (right, 0.0, self.width - right, self.height),
+        ]
+        .into_iter()
+        .filter(|&(_, _, w, h)| w > 0.0 && h > 0.0)
+    }
+}
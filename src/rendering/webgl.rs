@@ -0,0 +1,637 @@
+//! Batched WebGL2 `RenderBackend`
+//!
+//! Every shape is tessellated on the CPU into coloured triangles and
+//! appended to one vertex buffer; the batch is drawn with a single
+//! `drawArrays` whenever the blend mode changes or the frame ends. That
+//! keeps thousands of particles at one draw call instead of thousands of
+//! canvas path fills.
+//!
+//! Glow draws go to a reduced-size framebuffer texture. `end_glow` blurs it
+//! with separable gaussian passes, ping-ponging between two textures, and
+//! adds the result to the screen with a fullscreen triangle.
+//!
+//! WebGL has no text rendering, so text goes to an offscreen 2D canvas that
+//! is uploaded as a texture and drawn over the frame. Pending text is
+//! composited as soon as geometry is drawn after it (and before glow, the
+//! CRT pass and the end of the frame), so text and shapes layer in the
+//! order they were drawn: a menu's backdrop covers the HUD text under it.
+//! That costs one upload per switch from text back to shapes, a handful a
+//! frame.
+//!
+//! The CRT pass copies the finished frame into a texture and redraws it
+//! through a shader that mirrors `crt::apply`.
+
+use std::f64::consts::TAU;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, WebGl2RenderingContext as Gl, WebGlBuffer,
+    WebGlFramebuffer, WebGlProgram, WebGlShader, WebGlTexture, WebGlUniformLocation,
+    WebGlVertexArrayObject,
+};
+use crate::rendering::backend::{BlendMode, Color, RenderBackend, TextAlign, Transform, TransformStack};
+use crate::rendering::crt::CrtParams;
+use crate::rendering::glow::GlowParams;
+
+/// x, y, r, g, b, a
+const FLOATS_PER_VERTEX: usize = 6;
+
+const VERTEX_SHADER: &str = r#"#version 300 es
+in vec2 a_position;
+in vec4 a_color;
+uniform vec2 u_resolution;
+out vec4 v_color;
+void main() {
+    vec2 clip = a_position / u_resolution * 2.0 - 1.0;
+    gl_Position = vec4(clip.x, -clip.y, 0.0, 1.0);
+    v_color = a_color;
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec4 v_color;
+out vec4 out_color;
+void main() {
+    out_color = v_color;
+}
+"#;
+
+/// Fullscreen triangle from `gl_VertexID`, no vertex buffer needed
+const POST_VERTEX_SHADER: &str = r#"#version 300 es
+out vec2 v_uv;
+void main() {
+    vec2 pos = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    v_uv = pos;
+    gl_Position = vec4(pos * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+/// 9-tap gaussian along `u_step`; a zero step is a plain scaled copy
+const BLUR_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+uniform sampler2D u_texture;
+uniform vec2 u_step;
+uniform float u_intensity;
+in vec2 v_uv;
+out vec4 out_color;
+const float WEIGHTS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+void main() {
+    vec4 sum = texture(u_texture, v_uv) * WEIGHTS[0];
+    for (int i = 1; i < 5; i++) {
+        vec2 offset = u_step * float(i);
+        sum += (texture(u_texture, v_uv + offset) + texture(u_texture, v_uv - offset)) * WEIGHTS[i];
+    }
+    out_color = sum * u_intensity;
+}
+"#;
+
+/// Same maths as `crt::apply`, in top-down coordinates like the CPU version
+const CRT_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_curvature;
+uniform float u_aberration;
+uniform float u_scanlines;
+uniform float u_line_height;
+uniform float u_vignette;
+uniform float u_brightness;
+in vec2 v_uv;
+out vec4 out_color;
+vec4 tap(vec2 p) {
+    vec2 uv = (p + 1.0) * 0.5;
+    if (uv.x < 0.0 || uv.y < 0.0 || uv.x >= 1.0 || uv.y >= 1.0) {
+        return vec4(0.0);
+    }
+    return texture(u_texture, vec2(uv.x, 1.0 - uv.y));
+}
+void main() {
+    vec2 c = vec2(v_uv.x, 1.0 - v_uv.y) * 2.0 - 1.0;
+    float r2 = dot(c, c);
+    vec2 p = c * (1.0 + u_curvature * r2);
+    float ab = p.x * u_aberration / u_resolution.x * 2.0;
+    vec3 rgb = vec3(tap(p + vec2(ab, 0.0)).r, tap(p).g, tap(p - vec2(ab, 0.0)).b);
+    float row = floor((p.y + 1.0) * 0.5 * u_resolution.y / u_line_height);
+    float scan = mod(row, 2.0) == 1.0 ? 1.0 - u_scanlines : 1.0;
+    float vignette = max(1.0 - u_vignette * r2 * 0.5, 0.0);
+    out_color = vec4(rgb * scan * vignette * u_brightness, 1.0);
+}
+"#;
+
+/// Texture with a framebuffer attached, for offscreen passes
+struct RenderTarget {
+    texture: WebGlTexture,
+    framebuffer: WebGlFramebuffer,
+}
+
+/// Fullscreen single-texture pass: blur, or a scaled copy with a zero step
+struct PostProgram {
+    program: WebGlProgram,
+    vao: WebGlVertexArrayObject,
+    u_step: Option<WebGlUniformLocation>,
+    u_intensity: Option<WebGlUniformLocation>,
+}
+
+/// Ping-pong targets for the glow blur, created on first use
+struct GlowTargets {
+    targets: [RenderTarget; 2],
+    size: (i32, i32),
+}
+
+/// CRT shader and the copy of the frame it reads, created on first use
+struct CrtPipeline {
+    program: WebGlProgram,
+    frame: WebGlTexture,
+    uniforms: [Option<WebGlUniformLocation>; 7],
+}
+
+pub struct WebGl2Backend {
+    gl: Gl,
+    program: WebGlProgram,
+    vbo: WebGlBuffer,
+    vao: WebGlVertexArrayObject,
+    u_resolution: Option<WebGlUniformLocation>,
+    post: PostProgram,
+    text_canvas: HtmlCanvasElement,
+    text: CanvasRenderingContext2d,
+    text_texture: WebGlTexture,
+    /// Something was drawn on the text canvas since it was last uploaded
+    text_dirty: bool,
+    width: f64,
+    height: f64,
+    vertices: Vec<f32>,
+    transform: TransformStack,
+    blend: BlendMode,
+    glow_targets: Option<GlowTargets>,
+    glow: Option<GlowParams>,
+    crt: Option<CrtPipeline>,
+}
+
+impl WebGl2Backend {
+    /// Returns `Err` if the browser has no WebGL2, so the caller can fall back
+    pub fn new(canvas: &HtmlCanvasElement, width: f64, height: f64) -> Result<Self, JsValue> {
+        let gl: Gl = canvas
+            .get_context("webgl2")?
+            .ok_or("WebGL2 unavailable")?
+            .dyn_into()?;
+
+        let program = link_program(&gl)?;
+        let vbo = gl.create_buffer().ok_or("failed to create vertex buffer")?;
+        let vao = gl.create_vertex_array().ok_or("failed to create vertex array")?;
+
+        gl.bind_vertex_array(Some(&vao));
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&vbo));
+        let stride = (FLOATS_PER_VERTEX * 4) as i32;
+        let position = gl.get_attrib_location(&program, "a_position") as u32;
+        let color = gl.get_attrib_location(&program, "a_color") as u32;
+        gl.enable_vertex_attrib_array(position);
+        gl.vertex_attrib_pointer_with_i32(position, 2, Gl::FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(color);
+        gl.vertex_attrib_pointer_with_i32(color, 4, Gl::FLOAT, false, stride, 8);
+
+        let u_resolution = gl.get_uniform_location(&program, "u_resolution");
+
+        let post_program = link(&gl, POST_VERTEX_SHADER, BLUR_FRAGMENT_SHADER)?;
+        let post = PostProgram {
+            vao: gl.create_vertex_array().ok_or("failed to create vertex array")?,
+            u_step: gl.get_uniform_location(&post_program, "u_step"),
+            u_intensity: gl.get_uniform_location(&post_program, "u_intensity"),
+            program: post_program,
+        };
+        let (text_canvas, text) = create_text_layer(canvas, width, height)?;
+
+        Ok(WebGl2Backend {
+            post,
+            text_canvas,
+            text,
+            text_texture: create_texture(&gl)?,
+            text_dirty: false,
+            gl,
+            program,
+            vbo,
+            vao,
+            u_resolution,
+            width,
+            height,
+            vertices: Vec::with_capacity(64 * 1024),
+            transform: TransformStack::default(),
+            blend: BlendMode::Normal,
+            glow_targets: None,
+            glow: None,
+            crt: None,
+        })
+    }
+
+    fn flush(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        let gl = &self.gl;
+        gl.use_program(Some(&self.program));
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.vbo));
+        gl.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &js_sys::Float32Array::from(self.vertices.as_slice()),
+            Gl::STREAM_DRAW,
+        );
+        gl.uniform2f(self.u_resolution.as_ref(), self.width as f32, self.height as f32);
+        gl.draw_arrays(Gl::TRIANGLES, 0, (self.vertices.len() / FLOATS_PER_VERTEX) as i32);
+        self.vertices.clear();
+    }
+
+    /// Glow render targets of `size`, built or resized as needed
+    fn glow_targets(&mut self, size: (i32, i32)) -> Result<&GlowTargets, JsValue> {
+        let gl = &self.gl;
+        if self.glow_targets.is_none() {
+            self.glow_targets = Some(GlowTargets {
+                targets: [create_render_target(gl)?, create_render_target(gl)?],
+                size: (0, 0),
+            });
+        }
+        let glow = self.glow_targets.as_mut().ok_or("no glow targets")?;
+        if glow.size != size {
+            for target in &glow.targets {
+                allocate_texture(gl, &target.texture, size)?;
+            }
+            glow.size = size;
+        }
+        Ok(glow)
+    }
+
+    /// One fullscreen pass sampling `source` into whatever framebuffer is bound
+    fn post_pass(&self, source: &WebGlTexture, step: (f32, f32), intensity: f32) {
+        let gl = &self.gl;
+        gl.use_program(Some(&self.post.program));
+        gl.bind_vertex_array(Some(&self.post.vao));
+        gl.active_texture(Gl::TEXTURE0);
+        gl.bind_texture(Gl::TEXTURE_2D, Some(source));
+        gl.uniform2f(self.post.u_step.as_ref(), step.0, step.1);
+        gl.uniform1f(self.post.u_intensity.as_ref(), intensity);
+        gl.draw_arrays(Gl::TRIANGLES, 0, 3);
+    }
+
+    /// Upload the text canvas, draw it over the frame and clear it for
+    /// whatever text comes next
+    fn composite_text(&mut self) {
+        if !self.text_dirty {
+            return;
+        }
+        self.flush();
+        self.text_dirty = false;
+
+        let gl = &self.gl;
+        gl.bind_texture(Gl::TEXTURE_2D, Some(&self.text_texture));
+        gl.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 1);
+        gl.pixel_storei(Gl::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 1);
+        let uploaded = gl.tex_image_2d_with_u32_and_u32_and_html_canvas_element(
+            Gl::TEXTURE_2D, 0, Gl::RGBA as i32, Gl::RGBA, Gl::UNSIGNED_BYTE, &self.text_canvas,
+        );
+        gl.pixel_storei(Gl::UNPACK_FLIP_Y_WEBGL, 0);
+        gl.pixel_storei(Gl::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 0);
+        if uploaded.is_ok() {
+            gl.blend_func(Gl::ONE, Gl::ONE_MINUS_SRC_ALPHA);
+            self.post_pass(&self.text_texture, (0.0, 0.0), 1.0);
+            self.apply_blend();
+        }
+        self.clear_text();
+    }
+
+    fn clear_text(&self) {
+        let _ = self.text.reset_transform();
+        self.text.clear_rect(0.0, 0.0, self.width, self.height);
+    }
+
+    /// Build the CRT pipeline on first use
+    fn ensure_crt_pipeline(&mut self) -> Result<(), JsValue> {
+        if self.crt.is_none() {
+            let gl = &self.gl;
+            let program = link(gl, POST_VERTEX_SHADER, CRT_FRAGMENT_SHADER)?;
+            let frame = create_texture(gl)?;
+            allocate_texture(gl, &frame, (self.width as i32, self.height as i32))?;
+            let uniforms = ["u_resolution", "u_curvature", "u_aberration", "u_scanlines", "u_line_height", "u_vignette", "u_brightness"]
+                .map(|name| gl.get_uniform_location(&program, name));
+            self.crt = Some(CrtPipeline { program, frame, uniforms });
+        }
+        Ok(())
+    }
+
+    fn apply_blend(&self) {
+        match self.blend {
+            BlendMode::Normal => self.gl.blend_func(Gl::SRC_ALPHA, Gl::ONE_MINUS_SRC_ALPHA),
+            BlendMode::Additive => self.gl.blend_func(Gl::SRC_ALPHA, Gl::ONE),
+        }
+    }
+
+    /// Append a vertex already in screen space
+    fn push_vertex(&mut self, (x, y): (f64, f64), [r, g, b, a]: [f32; 4]) {
+        self.vertices.extend_from_slice(&[x as f32, y as f32, r, g, b, a]);
+    }
+
+    fn triangle(&mut self, p0: (f64, f64), p1: (f64, f64), p2: (f64, f64), color: [f32; 4]) {
+        // Text drawn before this shape belongs under it
+        self.composite_text();
+        let t = self.transform.current;
+        self.push_vertex(t.apply(p0.0, p0.1), color);
+        self.push_vertex(t.apply(p1.0, p1.1), color);
+        self.push_vertex(t.apply(p2.0, p2.1), color);
+    }
+
+    fn quad(&mut self, corners: [(f64, f64); 4], color: [f32; 4]) {
+        self.triangle(corners[0], corners[1], corners[2], color);
+        self.triangle(corners[0], corners[2], corners[3], color);
+    }
+
+    /// Enough segments that a circle looks round at its on-screen size
+    fn segments_for(&self, radius: f64) -> usize {
+        let on_screen = radius * self.transform.current.scale_factor();
+        (on_screen * 0.7).clamp(8.0, 64.0) as usize
+    }
+}
+
+impl RenderBackend for WebGl2Backend {
+    fn begin_frame(&mut self) {
+        self.glow = None;
+        self.text_dirty = false;
+        self.gl.bind_framebuffer(Gl::FRAMEBUFFER, None);
+        self.transform.reset();
+        self.vertices.clear();
+        self.gl.viewport(0, 0, self.width as i32, self.height as i32);
+        self.gl.enable(Gl::BLEND);
+        self.blend = BlendMode::Normal;
+        self.apply_blend();
+        self.clear_text();
+    }
+
+    fn end_frame(&mut self) {
+        self.flush();
+        self.composite_text();
+    }
+
+    fn size(&self) -> (f64, f64) {
+        (self.width, self.height)
+    }
+
+    fn resize(&mut self, width: f64, height: f64) {
+        self.width = width;
+        self.height = height;
+        self.text_canvas.set_width(width as u32);
+        self.text_canvas.set_height(height as u32);
+        // The frame copy must match the drawing buffer
+        if let Some(crt) = &self.crt {
+            let _ = allocate_texture(&self.gl, &crt.frame, (width as i32, height as i32));
+        }
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.vertices.clear();
+        let [r, g, b, a] = color.to_f32();
+        self.gl.clear_color(r, g, b, a);
+        self.gl.clear(Gl::COLOR_BUFFER_BIT);
+    }
+
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Color) {
+        self.quad([(x, y), (x + w, y), (x + w, y + h), (x, y + h)], color.to_f32());
+    }
+
+    fn fill_circle(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+        let color = color.to_f32();
+        let segments = self.segments_for(radius);
+        let mut prev = (x + radius, y);
+        for i in 1..=segments {
+            let angle = i as f64 / segments as f64 * TAU;
+            let next = (x + angle.cos() * radius, y + angle.sin() * radius);
+            self.triangle((x, y), prev, next, color);
+            prev = next;
+        }
+    }
+
+    fn stroke_circle(&mut self, x: f64, y: f64, radius: f64, width: f64, color: Color) {
+        let color = color.to_f32();
+        let (inner, outer) = ((radius - width / 2.0).max(0.0), radius + width / 2.0);
+        let segments = self.segments_for(outer);
+        for i in 0..segments {
+            let a0 = i as f64 / segments as f64 * TAU;
+            let a1 = (i + 1) as f64 / segments as f64 * TAU;
+            let (c0, s0) = (a0.cos(), a0.sin());
+            let (c1, s1) = (a1.cos(), a1.sin());
+            self.quad(
+                [
+                    (x + c0 * inner, y + s0 * inner),
+                    (x + c0 * outer, y + s0 * outer),
+                    (x + c1 * outer, y + s1 * outer),
+                    (x + c1 * inner, y + s1 * inner),
+                ],
+                color,
+            );
+        }
+    }
+
+    fn fill_polygon(&mut self, points: &[(f64, f64)], color: Color) {
+        let color = color.to_f32();
+        for i in 1..points.len().saturating_sub(1) {
+            self.triangle(points[0], points[i], points[i + 1], color);
+        }
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, width: f64, color: Color) {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let len = dx.hypot(dy);
+        if len == 0.0 {
+            return;
+        }
+        let (nx, ny) = (-dy / len * width / 2.0, dx / len * width / 2.0);
+        self.quad(
+            [(x1 + nx, y1 + ny), (x2 + nx, y2 + ny), (x2 - nx, y2 - ny), (x1 - nx, y1 - ny)],
+            color.to_f32(),
+        );
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, align: TextAlign, color: Color) {
+        let Transform { a, b, c, d, e, f } = self.transform.current;
+        let _ = self.text.set_transform(a, b, c, d, e, f);
+        self.text.set_font(&format!("{size}px Arial"));
+        self.text.set_text_align(match align {
+            TextAlign::Left => "left",
+            TextAlign::Center => "center",
+            TextAlign::Right => "right",
+        });
+        self.text.set_global_alpha(color.a as f64);
+        self.text.set_fill_style_str(&format!("rgb({}, {}, {})", color.r, color.g, color.b));
+        let _ = self.text.fill_text(text, x, y);
+        self.text_dirty = true;
+    }
+
+    fn set_blend_mode(&mut self, mode: BlendMode) {
+        if mode != self.blend {
+            self.flush();
+            self.blend = mode;
+            self.apply_blend();
+        }
+    }
+
+    fn begin_glow(&mut self, params: GlowParams) {
+        self.composite_text();
+        self.flush();
+        let size = (
+            (self.width / params.downscale).ceil() as i32,
+            (self.height / params.downscale).ceil() as i32,
+        );
+        let Ok(glow) = self.glow_targets(size) else { return };
+        let framebuffer = glow.targets[0].framebuffer.clone();
+
+        // Same screen-space vertices; the smaller viewport does the shrinking
+        let gl = &self.gl;
+        gl.bind_framebuffer(Gl::FRAMEBUFFER, Some(&framebuffer));
+        gl.viewport(0, 0, size.0, size.1);
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(Gl::COLOR_BUFFER_BIT);
+        self.glow = Some(params);
+    }
+
+    fn end_glow(&mut self) {
+        let Some(params) = self.glow.take() else { return };
+        self.flush();
+        let Some(glow) = self.glow_targets.take() else { return };
+        let gl = &self.gl;
+        let (w, h) = glow.size;
+        let [a, b] = &glow.targets;
+
+        // Spread the four taps either side over the blur radius, in layer texels
+        let spread = (params.radius / params.downscale / 4.0) as f32;
+        gl.disable(Gl::BLEND);
+        for _ in 0..params.passes {
+            gl.bind_framebuffer(Gl::FRAMEBUFFER, Some(&b.framebuffer));
+            self.post_pass(&a.texture, (spread / w as f32, 0.0), 1.0);
+            gl.bind_framebuffer(Gl::FRAMEBUFFER, Some(&a.framebuffer));
+            self.post_pass(&b.texture, (0.0, spread / h as f32), 1.0);
+        }
+
+        gl.bind_framebuffer(Gl::FRAMEBUFFER, None);
+        gl.viewport(0, 0, self.width as i32, self.height as i32);
+        gl.enable(Gl::BLEND);
+        gl.blend_func(Gl::ONE, Gl::ONE);
+        self.post_pass(&a.texture, (0.0, 0.0), params.intensity);
+        self.apply_blend();
+        self.glow_targets = Some(glow);
+    }
+
+    fn apply_crt(&mut self, params: CrtParams) {
+        self.flush();
+        self.composite_text();
+        let (width, height) = (self.width as i32, self.height as i32);
+        if self.ensure_crt_pipeline().is_err() {
+            return;
+        }
+        let Some(crt) = self.crt.as_ref() else { return };
+        let gl = &self.gl;
+
+        gl.bind_framebuffer(Gl::FRAMEBUFFER, None);
+        gl.bind_texture(Gl::TEXTURE_2D, Some(&crt.frame));
+        gl.copy_tex_sub_image_2d(Gl::TEXTURE_2D, 0, 0, 0, 0, 0, width, height);
+
+        gl.disable(Gl::BLEND);
+        gl.use_program(Some(&crt.program));
+        gl.bind_vertex_array(Some(&self.post.vao));
+        let [resolution, curvature, aberration, scanlines, line_height, vignette, brightness] = &crt.uniforms;
+        gl.uniform2f(resolution.as_ref(), width as f32, height as f32);
+        gl.uniform1f(curvature.as_ref(), params.curvature);
+        gl.uniform1f(aberration.as_ref(), params.aberration);
+        gl.uniform1f(scanlines.as_ref(), params.scanlines);
+        gl.uniform1f(line_height.as_ref(), params.line_height);
+        gl.uniform1f(vignette.as_ref(), params.vignette);
+        gl.uniform1f(brightness.as_ref(), params.brightness);
+        gl.draw_arrays(Gl::TRIANGLES, 0, 3);
+        gl.enable(Gl::BLEND);
+    }
+
+    fn push_transform(&mut self) {
+        self.transform.push();
+    }
+
+    fn pop_transform(&mut self) {
+        self.transform.pop();
+    }
+
+    fn translate(&mut self, x: f64, y: f64) {
+        self.transform.apply(&Transform::translation(x, y));
+    }
+
+    fn rotate(&mut self, angle: f64) {
+        self.transform.apply(&Transform::rotation(angle));
+    }
+
+    fn scale(&mut self, sx: f64, sy: f64) {
+        self.transform.apply(&Transform::scaling(sx, sy));
+    }
+}
+
+fn compile_shader(gl: &Gl, kind: u32, source: &str) -> Result<WebGlShader, JsValue> {
+    let shader = gl.create_shader(kind).ok_or("failed to create shader")?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+    if gl.get_shader_parameter(&shader, Gl::COMPILE_STATUS).as_bool().unwrap_or(false) {
+        Ok(shader)
+    } else {
+        Err(gl.get_shader_info_log(&shader).unwrap_or_default().into())
+    }
+}
+
+fn link_program(gl: &Gl) -> Result<WebGlProgram, JsValue> {
+    link(gl, VERTEX_SHADER, FRAGMENT_SHADER)
+}
+
+fn link(gl: &Gl, vertex_source: &str, fragment_source: &str) -> Result<WebGlProgram, JsValue> {
+    let vertex = compile_shader(gl, Gl::VERTEX_SHADER, vertex_source)?;
+    let fragment = compile_shader(gl, Gl::FRAGMENT_SHADER, fragment_source)?;
+    let program = gl.create_program().ok_or("failed to create program")?;
+    gl.attach_shader(&program, &vertex);
+    gl.attach_shader(&program, &fragment);
+    gl.link_program(&program);
+    if gl.get_program_parameter(&program, Gl::LINK_STATUS).as_bool().unwrap_or(false) {
+        Ok(program)
+    } else {
+        Err(gl.get_program_info_log(&program).unwrap_or_default().into())
+    }
+}
+
+fn create_texture(gl: &Gl) -> Result<WebGlTexture, JsValue> {
+    let texture = gl.create_texture().ok_or("failed to create texture")?;
+    gl.bind_texture(Gl::TEXTURE_2D, Some(&texture));
+    gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_MIN_FILTER, Gl::LINEAR as i32);
+    gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_MAG_FILTER, Gl::LINEAR as i32);
+    gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_S, Gl::CLAMP_TO_EDGE as i32);
+    gl.tex_parameteri(Gl::TEXTURE_2D, Gl::TEXTURE_WRAP_T, Gl::CLAMP_TO_EDGE as i32);
+    allocate_texture(gl, &texture, (1, 1))?;
+    Ok(texture)
+}
+
+/// (Re)allocate uninitialised RGBA8 storage of `size`
+fn allocate_texture(gl: &Gl, texture: &WebGlTexture, size: (i32, i32)) -> Result<(), JsValue> {
+    gl.bind_texture(Gl::TEXTURE_2D, Some(texture));
+    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+        Gl::TEXTURE_2D, 0, Gl::RGBA8 as i32, size.0, size.1, 0, Gl::RGBA, Gl::UNSIGNED_BYTE, None,
+    )
+}
+
+fn create_render_target(gl: &Gl) -> Result<RenderTarget, JsValue> {
+    let texture = create_texture(gl)?;
+    let framebuffer = gl.create_framebuffer().ok_or("failed to create framebuffer")?;
+    gl.bind_framebuffer(Gl::FRAMEBUFFER, Some(&framebuffer));
+    gl.framebuffer_texture_2d(Gl::FRAMEBUFFER, Gl::COLOR_ATTACHMENT0, Gl::TEXTURE_2D, Some(&texture), 0);
+    gl.bind_framebuffer(Gl::FRAMEBUFFER, None);
+    Ok(RenderTarget { texture, framebuffer })
+}
+
+/// Offscreen 2D canvas the size of the GL canvas, for text
+fn create_text_layer(canvas: &HtmlCanvasElement, width: f64, height: f64) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    let document = canvas.owner_document().ok_or("canvas has no document")?;
+    let layer: HtmlCanvasElement = document.create_element("canvas")?.dyn_into()?;
+    layer.set_width(width as u32);
+    layer.set_height(height as u32);
+    let ctx = layer
+        .get_context("2d")?
+        .ok_or("2D context unavailable")?
+        .dyn_into()?;
+    Ok((layer, ctx))
+}
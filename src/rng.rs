@@ -0,0 +1,57 @@
+//! Small deterministic PRNG (xorshift64*)
+//!
+//! Good enough for gameplay randomness and sound variation, cheap, and —
+//! unlike `js_sys::Math::random` — seedable and usable off the web.
+
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Rng { state: seed.max(1) }
+    }
+
+    /// Seed from whatever entropy the platform offers
+    pub fn from_entropy() -> Self {
+        Rng::new(entropy_seed())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `[min, max)`
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_f64()
+    }
+
+    /// Uniform in `[-1, 1)`, for symmetric jitter
+    pub fn signed(&mut self) -> f64 {
+        self.range(-1.0, 1.0)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn entropy_seed() -> u64 {
+    (js_sys::Math::random() * u64::MAX as f64) as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn entropy_seed() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0x9E37_79B9_7F4A_7C15)
+}
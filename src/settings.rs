@@ -0,0 +1,120 @@
+//! Building blocks for player settings that persist across sessions
+//!
+//! Each settings struct is saved under its own storage key as
+//! `key=value;key=value`. Loading starts from the defaults and applies the
+//! pairs it understands, so a missing key, an unknown key or a malformed
+//! value only costs that one field its saved value.
+
+use std::cell::Cell;
+use crate::storage;
+
+/// A setting picked from a fixed list of options
+pub trait Choice: Copy + PartialEq + 'static {
+    /// Every option, in menu order
+    const ALL: &'static [Self];
+
+    /// Shown in menus; also the saved form, lowercased
+    fn label(self) -> &'static str;
+
+    /// Next option in `direction`, clamped at either end
+    fn step(self, direction: i32) -> Self {
+        let index = Self::ALL.iter().position(|&o| o == self).unwrap_or(0) as i32;
+        Self::ALL[(index + direction).clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+
+    /// Next option, wrapping from the last back to the first
+    fn cycle(self) -> Self {
+        let index = Self::ALL.iter().position(|&o| o == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The option whose label matches, ignoring case
+    fn parse(raw: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|o| o.label().eq_ignore_ascii_case(raw.trim()))
+    }
+
+    fn save_value(self) -> String {
+        self.label().to_ascii_lowercase()
+    }
+}
+
+/// A settings struct saved to storage
+pub trait Persisted: Copy + Default + 'static {
+    const STORAGE_KEY: &'static str;
+
+    /// Apply one saved pair, ignoring unknown keys and bad values
+    fn set(&mut self, key: &str, value: &str);
+
+    /// Pairs to save; `set` must understand every one
+    fn pairs(&self) -> Vec<(&'static str, String)>;
+
+    /// Pull values back into range after loading or changing them
+    fn sanitize(&mut self) {}
+
+    fn parse(raw: &str) -> Self {
+        let mut settings = Self::default();
+        for pair in raw.split(';') {
+            let Some((key, value)) = pair.split_once('=') else { continue };
+            settings.set(key.trim(), value.trim());
+        }
+        settings.sanitize();
+        settings
+    }
+
+    fn serialize(&self) -> String {
+        self.pairs().iter().map(|(key, value)| format!("{key}={value}")).collect::<Vec<_>>().join(";")
+    }
+}
+
+/// One `Persisted` value, loaded on first use and saved on every change.
+/// Keep it in a `thread_local!`.
+pub struct Persistent<T>(Cell<Option<T>>);
+
+impl<T: Persisted> Default for Persistent<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Persisted> Persistent<T> {
+    pub const fn new() -> Self {
+        Persistent(Cell::new(None))
+    }
+
+    pub fn get(&self) -> T {
+        if let Some(settings) = self.0.get() {
+            return settings;
+        }
+        let settings = storage::load(T::STORAGE_KEY).map(|raw| T::parse(&raw)).unwrap_or_default();
+        self.0.set(Some(settings));
+        settings
+    }
+
+    /// Mutate, sanitize and save; returns the new value
+    pub fn update(&self, f: impl FnOnce(&mut T)) -> T {
+        let mut settings = self.get();
+        f(&mut settings);
+        settings.sanitize();
+        self.0.set(Some(settings));
+        storage::save(T::STORAGE_KEY, &settings.serialize());
+        settings
+    }
+}
+
+/// `1` or `0`
+pub fn parse_flag(value: &str) -> Option<bool> {
+    match value {
+        "1" => Some(true),
+        "0" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn flag(on: bool) -> String {
+    (on as u8).to_string()
+}
+
+/// A finite number; `NaN` and infinities are malformed
+pub fn parse_number(value: &str) -> Option<f32> {
+    value.parse::<f32>().ok().filter(|v| v.is_finite())
+}
@@ -0,0 +1,60 @@
+//! Tiny persistence layer over `localStorage`, or a save directory in
+//! native builds
+//!
+//! Values are plain strings; callers own their own (simple) formats.
+//! Storage can be unavailable (private browsing, disabled cookies, no home
+//! directory), in which case loads return `None` and saves are silently
+//! dropped. Unit tests, and headless runs without the `native` feature
+//! (benches), never persist anything, so they can't read or overwrite a
+//! player's real saves.
+
+const KEY_PREFIX: &str = "renegade.";
+
+#[cfg(any(not(feature = "native"), test))]
+fn local_storage() -> Option<web_sys::Storage> {
+    // Headless runs (tests, benches) have no browser to ask
+    if !cfg!(target_arch = "wasm32") {
+        return None;
+    }
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(any(not(feature = "native"), test))]
+pub fn load(key: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("{KEY_PREFIX}{key}")).ok()?
+}
+
+#[cfg(any(not(feature = "native"), test))]
+pub fn save(key: &str, value: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(&format!("{KEY_PREFIX}{key}"), value);
+    }
+}
+
+/// One file per key under the platform's per-user data directory:
+/// `$XDG_DATA_HOME/renegade`, `~/.local/share/renegade` or
+/// `%APPDATA%\renegade`
+#[cfg(all(feature = "native", not(test)))]
+fn save_path(key: &str) -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let env = |name| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = env("XDG_DATA_HOME")
+        .or_else(|| env("APPDATA"))
+        .or_else(|| env("HOME").map(|home| home.join(".local/share")))?;
+    Some(base.join("renegade").join(format!("{KEY_PREFIX}{key}")))
+}
+
+#[cfg(all(feature = "native", not(test)))]
+pub fn load(key: &str) -> Option<String> {
+    std::fs::read_to_string(save_path(key)?).ok()
+}
+
+#[cfg(all(feature = "native", not(test)))]
+pub fn save(key: &str, value: &str) {
+    let Some(path) = save_path(key) else { return };
+    let written = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|()| std::fs::write(&path, value));
+    if let Err(err) = written {
+        log::warn!("Couldn't save {}: {}", path.display(), err);
+    }
+}
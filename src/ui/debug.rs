@@ -0,0 +1,197 @@
+//! Developer overlay (F3): frame timing, entity counts, spawn pacing and
+//! collision shapes, plus a panel of `GameConfig` values that can be
+//! nudged while the game runs. Changes apply to the live game only; copy
+//! the shown key and value into a config file or the URL to keep them.
+
+use crate::audio;
+use crate::game::GameConfig;
+
+/// Frames kept for the FPS average and the frame-time graph
+pub const FRAME_HISTORY: usize = 120;
+
+/// Where a tweak's value lives in the config
+#[derive(Clone, Copy)]
+enum Field {
+    F64(fn(&mut GameConfig) -> &mut f64),
+    I32(fn(&mut GameConfig) -> &mut i32),
+    U32(fn(&mut GameConfig) -> &mut u32),
+}
+
+/// One editable row of the panel
+#[derive(Clone, Copy)]
+pub struct Tweak {
+    /// Config key, as it would be written in an override
+    pub key: &'static str,
+    step: f64,
+    field: Field,
+}
+
+macro_rules! tweak {
+    ($key:literal, $step:expr, $kind:ident, |$c:ident| $place:expr) => {
+        Tweak { key: $key, step: $step, field: Field::$kind(|$c| &mut $place) }
+    };
+}
+
+pub const TWEAKS: [Tweak; 17] = [
+    tweak!("player.turn_speed", 0.005, F64, |c| c.player.turn_speed),
+    tweak!("player.idle_speed", 0.005, F64, |c| c.player.idle_speed),
+    tweak!("projectile.speed", 1.0, F64, |c| c.projectile.speed),
+    tweak!("projectile.radius", 1.0, F64, |c| c.projectile.radius),
+    tweak!("planet.impact_damage", 1.0, I32, |c| c.planet.impact_damage),
+    tweak!("threats.speed[0]", 0.1, F64, |c| c.threats.speed[0]),
+    tweak!("threats.speed[1]", 0.1, F64, |c| c.threats.speed[1]),
+    tweak!("threats.radius[0]", 1.0, F64, |c| c.threats.radius[0]),
+    tweak!("threats.radius[1]", 1.0, F64, |c| c.threats.radius[1]),
+    tweak!("waves.base_size", 1.0, U32, |c| c.waves.base_size),
+    tweak!("waves.growth", 1.0, U32, |c| c.waves.growth),
+    tweak!("waves.spawn_interval[0]", 0.1, F64, |c| c.waves.spawn_interval[0]),
+    tweak!("waves.spawn_interval[1]", 0.1, F64, |c| c.waves.spawn_interval[1]),
+    tweak!("waves.ramp_secs", 5.0, F64, |c| c.waves.ramp_secs),
+    tweak!("combo.points", 1.0, U32, |c| c.combo.points),
+    tweak!("combo.window_secs", 0.25, F64, |c| c.combo.window_secs),
+    tweak!("combo.max_multiplier", 1.0, U32, |c| c.combo.max_multiplier),
+];
+
+impl Tweak {
+    /// Current value, formatted for the panel
+    pub fn value(&self, config: &GameConfig) -> String {
+        // The accessors need `&mut`; the config is plain data, so a copy is cheap
+        let mut config = config.clone();
+        match self.field {
+            Field::F64(place) => format!("{:.3}", place(&mut config)),
+            Field::I32(place) => place(&mut config).to_string(),
+            Field::U32(place) => place(&mut config).to_string(),
+        }
+    }
+
+    /// Move the value one step; `false` (and no change) if that would
+    /// leave the config invalid
+    pub fn adjust(&self, config: &mut GameConfig, direction: f64) -> bool {
+        let mut tweaked = config.clone();
+        let delta = self.step * direction;
+        match self.field {
+            Field::F64(place) => {
+                let value = place(&mut tweaked);
+                // Snap to the step grid so repeated presses don't drift
+                *value = ((*value + delta) / self.step).round() * self.step;
+            }
+            Field::I32(place) => *place(&mut tweaked) += delta as i32,
+            Field::U32(place) => {
+                let value = place(&mut tweaked);
+                *value = value.saturating_add_signed(delta as i32);
+            }
+        }
+        if tweaked.validate().is_err() {
+            return false;
+        }
+        *config = tweaked;
+        true
+    }
+}
+
+pub struct DebugOverlay {
+    pub open: bool,
+    /// Highlighted row of `TWEAKS`
+    pub selected: usize,
+    /// Seconds per frame, a ring buffer written at `next`
+    frame_times: [f64; FRAME_HISTORY],
+    next: usize,
+    recorded: usize,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        DebugOverlay { open: false, selected: 0, frame_times: [0.0; FRAME_HISTORY], next: 0, recorded: 0 }
+    }
+}
+
+impl DebugOverlay {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        audio::ui_blip(if self.open { 1320.0 } else { 990.0 });
+    }
+
+    /// Called every frame, open or not, so the graph is full when it opens
+    pub fn record_frame(&mut self, dt: f64) {
+        self.frame_times[self.next] = dt;
+        self.next = (self.next + 1) % FRAME_HISTORY;
+        self.recorded = (self.recorded + 1).min(FRAME_HISTORY);
+    }
+
+    /// Recorded frame times, oldest first
+    pub fn frame_times(&self) -> impl Iterator<Item = f64> + '_ {
+        let start = (self.next + FRAME_HISTORY - self.recorded) % FRAME_HISTORY;
+        (0..self.recorded).map(move |i| self.frame_times[(start + i) % FRAME_HISTORY])
+    }
+
+    /// Average over the recorded history
+    pub fn fps(&self) -> f64 {
+        let total: f64 = self.frame_times().sum();
+        if total > 0.0 { self.recorded as f64 / total } else { 0.0 }
+    }
+
+    pub fn move_selection(&mut self, delta: i32) {
+        let len = TWEAKS.len() as i32;
+        self.selected = (self.selected as i32 + delta).rem_euclid(len) as usize;
+    }
+
+    /// Step the highlighted value up or down in the live config
+    pub fn adjust(&mut self, config: &mut GameConfig, direction: f64) {
+        let tweak = TWEAKS[self.selected];
+        if tweak.adjust(config, direction) {
+            audio::ui_blip(740.0);
+        } else {
+            audio::ui_blip(220.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tweaks_step_and_refuse_invalid_values() {
+        let mut config = GameConfig::default();
+        let speed = TWEAKS.iter().find(|t| t.key == "projectile.speed").unwrap();
+        assert!(speed.adjust(&mut config, 1.0));
+        assert_eq!(speed.value(&config), "9.000");
+
+        // The fastest threat can't drop below the slowest
+        let max_speed = TWEAKS.iter().find(|t| t.key == "threats.speed[1]").unwrap();
+        let floor = config.threats.speed[0];
+        while max_speed.adjust(&mut config, -1.0) {}
+        assert!(config.threats.speed[1] >= floor);
+
+        let base_size = TWEAKS.iter().find(|t| t.key == "waves.base_size").unwrap();
+        config.waves.base_size = 1;
+        assert!(!base_size.adjust(&mut config, -1.0), "waves.base_size can't reach 0");
+    }
+
+    #[test]
+    fn tweaks_stop_short_of_zero() {
+        let mut config = GameConfig::default();
+        let keys = ["projectile.speed", "projectile.radius", "waves.ramp_secs"];
+        for tweak in TWEAKS.iter().filter(|t| keys.contains(&t.key)) {
+            let mut steps = 0;
+            while tweak.adjust(&mut config, -1.0) {
+                steps += 1;
+                assert!(steps < 1000, "{} never stopped", tweak.key);
+            }
+            let value: f64 = tweak.value(&config).parse().unwrap();
+            assert!(value > 0.0, "{} stepped down to {value}", tweak.key);
+        }
+    }
+
+    #[test]
+    fn frame_history_wraps_oldest_first() {
+        let mut overlay = DebugOverlay::default();
+        for i in 0..FRAME_HISTORY + 5 {
+            overlay.record_frame(i as f64);
+        }
+        let times: Vec<f64> = overlay.frame_times().collect();
+        assert_eq!(times.len(), FRAME_HISTORY);
+        assert_eq!(times[0], 5.0);
+        assert_eq!(*times.last().unwrap(), (FRAME_HISTORY + 4) as f64);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod debug;
+pub mod options;
+pub mod summary;
+
+pub use debug::DebugOverlay;
+pub use options::OptionsMenu;
+pub use summary::RunSummary;
@@ -0,0 +1,152 @@
+//! In-game options menu (Esc / O). The simulation is paused while it is open.
+//! The achievements list opens from here too, as a screen over the menu.
+
+use crate::audio;
+use crate::game::{achievements, difficulty};
+use crate::rendering::settings as graphics;
+use crate::settings::Choice;
+
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OptionItem {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Mute,
+    Glow,
+    Crt,
+    Camera,
+    Difficulty,
+    Adaptive,
+    Achievements,
+}
+
+impl OptionItem {
+    pub const ALL: [OptionItem; 10] = [
+        OptionItem::MasterVolume,
+        OptionItem::MusicVolume,
+        OptionItem::SfxVolume,
+        OptionItem::Mute,
+        OptionItem::Glow,
+        OptionItem::Crt,
+        OptionItem::Camera,
+        OptionItem::Difficulty,
+        OptionItem::Adaptive,
+        OptionItem::Achievements,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OptionItem::MasterVolume => "Master Volume",
+            OptionItem::MusicVolume => "Music Volume",
+            OptionItem::SfxVolume => "Effects Volume",
+            OptionItem::Mute => "Mute",
+            OptionItem::Glow => "Glow",
+            OptionItem::Crt => "CRT Effect",
+            OptionItem::Camera => "Camera",
+            OptionItem::Difficulty => "Difficulty",
+            OptionItem::Adaptive => "Adaptive Pacing",
+            OptionItem::Achievements => "Achievements",
+        }
+    }
+
+    /// Current value as displayed next to the label
+    pub fn value(self) -> String {
+        let volume = audio::volume();
+        match self {
+            OptionItem::MasterVolume => percent(volume.master),
+            OptionItem::MusicVolume => percent(volume.music),
+            OptionItem::SfxVolume => percent(volume.sfx),
+            OptionItem::Mute => if volume.muted { "ON".into() } else { "OFF".into() },
+            OptionItem::Glow => graphics::settings().glow.label().into(),
+            OptionItem::Crt => if graphics::settings().crt { "ON".into() } else { "OFF".into() },
+            OptionItem::Camera => graphics::settings().camera.label().into(),
+            OptionItem::Difficulty => difficulty::settings().level.label().into(),
+            OptionItem::Adaptive => if difficulty::settings().adaptive { "ON".into() } else { "OFF".into() },
+            OptionItem::Achievements => {
+                format!("{}/{}", achievements::progress().len(), achievements::Achievement::ALL.len())
+            }
+        }
+    }
+}
+
+/// Move one notch up or down, snapping to the step grid so repeated presses
+/// don't accumulate float drift
+pub fn step_volume(level: f32, direction: f32) -> f32 {
+    ((level + VOLUME_STEP * direction) / VOLUME_STEP).round() * VOLUME_STEP
+}
+
+fn percent(level: f32) -> String {
+    format!("{:>3}%", (level * 100.0).round() as i32)
+}
+
+#[derive(Default)]
+pub struct OptionsMenu {
+    pub open: bool,
+    pub selected: usize,
+    /// The achievements screen is showing over the menu
+    pub achievements: bool,
+}
+
+impl OptionsMenu {
+    /// Esc backs out of the achievements screen before closing the menu
+    pub fn toggle(&mut self) {
+        if self.achievements {
+            self.achievements = false;
+            audio::ui_blip(660.0);
+            return;
+        }
+        self.open = !self.open;
+        audio::ui_blip(if self.open { 880.0 } else { 660.0 });
+    }
+
+    pub fn selected_item(&self) -> OptionItem {
+        OptionItem::ALL[self.selected]
+    }
+
+    pub fn move_selection(&mut self, delta: i32) {
+        let len = OptionItem::ALL.len() as i32;
+        self.selected = (self.selected as i32 + delta).rem_euclid(len) as usize;
+        audio::ui_blip(740.0);
+    }
+
+    /// Left/right on the highlighted row
+    pub fn adjust(&mut self, direction: f32) {
+        let volume = audio::volume();
+        let step = |level: f32| step_volume(level, direction);
+        match self.selected_item() {
+            OptionItem::MasterVolume => audio::set_master(step(volume.master)),
+            OptionItem::MusicVolume => audio::set_music(step(volume.music)),
+            OptionItem::SfxVolume => audio::set_sfx(step(volume.sfx)),
+            OptionItem::Mute => audio::toggle_mute(),
+            OptionItem::Glow => graphics::update(|s| s.glow = s.glow.step(direction as i32)),
+            OptionItem::Crt => graphics::update(|s| s.crt = !s.crt),
+            OptionItem::Camera => graphics::update(|s| s.camera = s.camera.cycle()),
+            OptionItem::Difficulty => difficulty::update(|s| s.level = s.level.step(direction as i32)),
+            OptionItem::Adaptive => difficulty::update(|s| s.adaptive = !s.adaptive),
+            OptionItem::Achievements => {
+                if direction > 0.0 {
+                    self.activate();
+                }
+                return;
+            }
+        }
+        audio::ui_blip(600.0 + 300.0 * audio::volume().sfx);
+    }
+
+    /// Enter on the highlighted row
+    pub fn activate(&mut self) {
+        match self.selected_item() {
+            OptionItem::Mute => audio::toggle_mute(),
+            OptionItem::Glow => graphics::update(|s| s.glow = s.glow.cycle()),
+            OptionItem::Crt => graphics::update(|s| s.crt = !s.crt),
+            OptionItem::Camera => graphics::update(|s| s.camera = s.camera.cycle()),
+            OptionItem::Difficulty => difficulty::update(|s| s.level = s.level.cycle()),
+            OptionItem::Adaptive => difficulty::update(|s| s.adaptive = !s.adaptive),
+            OptionItem::Achievements => self.achievements = true,
+            _ => return,
+        }
+        audio::ui_blip(880.0);
+    }
+}
@@ -0,0 +1,117 @@
+//! Game-over summary: the run's statistics over a few pages (Left/Right),
+//! with E to download them as JSON
+
+use crate::audio;
+use crate::game::GameState;
+use crate::settings::Choice;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SummaryPage {
+    #[default]
+    Overview,
+    Combat,
+    Damage,
+}
+
+impl SummaryPage {
+    pub const ALL: [SummaryPage; 3] = [SummaryPage::Overview, SummaryPage::Combat, SummaryPage::Damage];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            SummaryPage::Overview => "OVERVIEW",
+            SummaryPage::Combat => "COMBAT",
+            SummaryPage::Damage => "DAMAGE",
+        }
+    }
+
+    /// (label, value) lines for this page
+    pub fn rows(self, state: &GameState) -> Vec<(&'static str, String)> {
+        let stats = &state.stats;
+        match self {
+            SummaryPage::Overview => {
+                let difficulty = if state.adaptive.enabled {
+                    format!("{} (ADAPTIVE)", state.difficulty.label())
+                } else {
+                    state.difficulty.label().into()
+                };
+                let secs = state.time as u32;
+                vec![
+                    ("Final Score", state.score.to_string()),
+                    ("Time Survived", format!("{}:{:02}", secs / 60, secs % 60)),
+                    ("Waves Cleared", state.waves_cleared().to_string()),
+                    ("Difficulty", difficulty),
+                ]
+            }
+            SummaryPage::Combat => vec![
+                ("Shots Fired", stats.shots_fired.to_string()),
+                ("Hits", stats.hits.to_string()),
+                ("Accuracy", format!("{:.0}%", stats.accuracy() * 100.0)),
+                ("Best Hit Streak", stats.best_hit_streak.to_string()),
+                ("Max Combo", format!("{}x", stats.max_combo)),
+            ],
+            SummaryPage::Damage => vec![
+                ("Threats Destroyed", stats.threats_destroyed.to_string()),
+                ("Small Threats", stats.small_threats_destroyed.to_string()),
+                ("Large Threats", stats.large_threats_destroyed.to_string()),
+                ("Planet Damage Taken", stats.damage_taken.to_string()),
+            ],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RunSummary {
+    pub page: usize,
+}
+
+impl RunSummary {
+    pub fn current(&self) -> SummaryPage {
+        SummaryPage::ALL[self.page]
+    }
+
+    pub fn turn(&mut self, delta: i32) {
+        let len = SummaryPage::ALL.len() as i32;
+        self.page = (self.page as i32 + delta).rem_euclid(len) as usize;
+        audio::ui_blip(740.0);
+    }
+
+    /// Save the run's stats as a JSON file through the browser
+    pub fn export(&self, state: &GameState) {
+        let json = state.stats_json();
+        match download("renegade-run.json", &json) {
+            Ok(()) => audio::ui_blip(880.0),
+            Err(err) => log::error!("Stats export failed: {:?}", err),
+        }
+        log::info!("Run stats: {json}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn download(filename: &str, contents: &str) -> Result<(), wasm_bindgen::JsValue> {
+    use wasm_bindgen::JsCast;
+
+    let parts = js_sys::Array::of1(&contents.into());
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/json");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let document = web_sys::window().and_then(|w| w.document()).ok_or("no document")?;
+    let link: web_sys::HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    link.set_href(&url);
+    link.set_download(filename);
+    link.click();
+    // Some browsers start the download after click() returns; let it begin
+    // before the URL goes away
+    let revoke = wasm_bindgen::closure::Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    web_sys::window()
+        .ok_or("no window")?
+        .set_timeout_with_callback_and_timeout_and_arguments_0(revoke.unchecked_ref(), 0)?;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn download(filename: &str, contents: &str) -> std::io::Result<()> {
+    std::fs::write(filename, contents)
+}